@@ -13,9 +13,14 @@ use bevy::{
     },
 };
 use bevy_mesh::Indices;
+use std::collections::HashMap;
 use image::load_from_memory;
 use gltf;
 
+// Images loaded from the frontend, kept here so they stay alive while no material uses them (e.g. after clearing a texture slot)
+#[derive(Resource, Default)]
+pub struct LoadedImages(pub Vec<Handle<Image>>);
+
 pub fn loadImage(mut images: ResMut<Assets<Image>>, buffer: &[u8]) -> Result<Handle<Image>, String> {
     let decodedImage = load_from_memory(buffer).expect("Failed to decode image").to_rgba8();
     let dimensions = decodedImage.dimensions();
@@ -41,14 +46,23 @@ pub fn loadImage(mut images: ResMut<Assets<Image>>, buffer: &[u8]) -> Result<Han
     return Ok(images.add(image));
 }
 
-pub fn loadModel(mut meshes: ResMut<Assets<Mesh>>, buffer: &[u8]) -> Result<Vec<Handle<Mesh>>, String> {
+// Meshes are named after the glTF node using them, or the glTF mesh itself when no node has a name
+pub fn loadModel(mut meshes: ResMut<Assets<Mesh>>, buffer: &[u8]) -> Result<Vec<(String, Handle<Mesh>)>, String> {
     let file = gltf::Gltf::from_slice(buffer).map_err(|e| format!("Failed to parse model data: {}", e))?;
     let buffers = gltf::import_buffers(&file, None, Some(file.blob.clone().unwrap())).map_err(|e| format!("Failed to get buffers from model data: {}", e))?;
     let _images = gltf::import_images(&file, None, &buffers).map_err(|e| format!("Failed to get images from model data: {}", e))?;
 
-    let mut col: Vec<Handle<Mesh>> = vec![];
+    let nodeNames: HashMap<usize, String> = file.nodes()
+        .filter_map(|node| Some((node.mesh()?.index(), node.name()?.to_string())))
+        .collect();
+
+    let mut col: Vec<(String, Handle<Mesh>)> = vec![];
 
     for mesh in file.meshes() {
+        let name = nodeNames.get(&mesh.index()).cloned()
+            .or_else(|| mesh.name().map(|name| name.to_string()))
+            .unwrap_or_else(|| "Mesh".to_string());
+
         for primitive in mesh.primitives() {
             let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
             let positions: Vec<[f32; 3]> = reader.read_positions().ok_or("Failed to read positions")?.collect();
@@ -70,7 +84,7 @@ pub fn loadModel(mut meshes: ResMut<Assets<Mesh>>, buffer: &[u8]) -> Result<Vec<
             }
 
 
-            col.push(meshes.add(bevyMesh));
+            col.push((name.clone(), meshes.add(bevyMesh)));
         }
     }
 
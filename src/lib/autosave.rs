@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+    editorconfig::EditorAutosaveConfiguration,
+    scene::SavedScene,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoverySnapshot {
+    // Position in the snapshot ring, the frontend overwrites whatever it stored in this slot before
+    pub slot: usize,
+    pub timestamp: f64,
+    pub reason: String,
+    pub scene: SavedScene,
+}
+
+#[derive(Resource)]
+pub struct AutosaveState {
+    pub timer: Timer,
+    pub nextSlot: usize,
+    pub lastHistoryLength: usize,
+    pub stepsSinceSave: usize,
+}
+
+impl AutosaveState {
+    pub fn new(config: &EditorAutosaveConfiguration) -> Self {
+        Self {
+            timer: Timer::from_seconds(config.interval, TimerMode::Repeating),
+            nextSlot: 0,
+            lastHistoryLength: 0,
+            stepsSinceSave: 0,
+        }
+    }
+
+    // Returns the reason for saving when a snapshot is due, undoing and redoing count as history steps as well
+    pub fn tick(&mut self, delta: std::time::Duration, historyLength: usize, config: &EditorAutosaveConfiguration) -> Option<&'static str> {
+        self.stepsSinceSave += historyLength.abs_diff(self.lastHistoryLength);
+        self.lastHistoryLength = historyLength;
+
+        if self.timer.tick(delta).just_finished() {
+            return Some("timer");
+        }
+        if config.historySteps > 0 && self.stepsSinceSave >= config.historySteps {
+            self.timer.reset();
+            return Some("history");
+        }
+
+        None
+    }
+
+    pub fn takeSlot(&mut self, config: &EditorAutosaveConfiguration) -> usize {
+        let slot = self.nextSlot;
+        self.nextSlot = (self.nextSlot + 1) % config.snapshotCount.max(1);
+        self.stepsSinceSave = 0;
+
+        return slot;
+    }
+}
+
+pub fn newestSnapshot(snapshots: &[RecoverySnapshot]) -> Option<&RecoverySnapshot> {
+    snapshots.iter().max_by(|a, b| a.timestamp.total_cmp(&b.timestamp))
+}
@@ -4,6 +4,30 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub struct RotationCamera;
 
+// Blender-style 3D cursor, a point in the scene that rotating and scaling can pivot around. Not part of the scene itself
+#[derive(Component)]
+pub struct EditorCursor;
+
+// Identifier that stays the same across saves and sessions, unlike `Entity`, so saved scenes can be matched up with each other
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[reflect(Component)]
+pub struct EditorId(pub u64);
+
+// Editor-only flags saved with the scene. Hidden entities aren't drawn in the editor while their `Visibility` stays what the game set it to
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component, Default)]
+pub struct EditorHidden;
+
+// Locked entities can't be picked in the viewport, e.g. the floor
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component, Default)]
+pub struct EditorLocked;
+
+// Free-form labels to find entities by in the editor's search, e.g. "enemy" or "todo"
+#[derive(Component, Reflect, Clone, Default, Debug)]
+#[reflect(Component, Default)]
+pub struct EditorTags(pub Vec<String>);
+
 
 // Components to mark needed usage of 
 #[derive(Component)]
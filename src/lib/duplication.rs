@@ -0,0 +1,131 @@
+use std::any::TypeId;
+
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::*,
+    reflect::TypeRegistry,
+};
+use bevy_mod_outline::{
+    OutlineStencil,
+    OutlineVolume,
+};
+use transform_gizmo_bevy::GizmoTarget;
+
+use crate::EditorId;
+
+use super::{
+    hierarchy::isAncestor,
+    naming::{takenNames, uniqueName},
+    selection::setSelected,
+};
+
+// Components that are not copied through reflection, as the hierarchy gets rebuilt for the copies, the selection state is set afterwards and copies get their own id
+fn isManuallyHandled(typeId: TypeId) -> bool {
+    typeId == TypeId::of::<Parent>()
+        || typeId == TypeId::of::<EditorId>()
+        || typeId == TypeId::of::<Children>()
+        || typeId == TypeId::of::<GizmoTarget>()
+        || typeId == TypeId::of::<OutlineStencil>()
+        || typeId == TypeId::of::<OutlineVolume>()
+}
+
+fn duplicateEntity(world: &mut World, registry: &TypeRegistry, source: Entity) -> Entity {
+    let components = world.inspect_entity(source)
+        .filter_map(|component| Some((component.type_id()?, component.name().to_string())))
+        .filter(|(typeId, _)| !isManuallyHandled(*typeId))
+        .collect::<Vec<(TypeId, String)>>();
+
+    // Cloning the reflected values clones any handles inside of them, meaning meshes and materials stay shared between the original and the copy
+    let mut values = vec![];
+    for (typeId, componentName) in components {
+        let reflected = registry.get_type_data::<ReflectComponent>(typeId)
+            .and_then(|reflectComponent| Some((reflectComponent.clone(), reflectComponent.reflect(world.entity(source))?.clone_value())));
+        match reflected {
+            Some(value) => values.push(value),
+            // Only reflected components can be copied, anything else the copy goes without
+            None => warn!("Duplicating {} without {}, as it isn't a reflected component", source, componentName),
+        }
+    }
+
+    let stencil = world.get::<OutlineStencil>(source).cloned();
+    let volume = world.get::<OutlineVolume>(source).cloned();
+    let children = world.get::<Children>(source).map(|children| children.to_vec());
+
+    let mut copyEntity = world.spawn_empty();
+    for (reflectComponent, value) in values.iter() {
+        reflectComponent.insert(&mut copyEntity, &**value, registry);
+    }
+    if let Some(stencil) = stencil {
+        copyEntity.insert(stencil);
+    }
+    if let Some(volume) = volume {
+        copyEntity.insert(volume);
+    }
+    let copy = copyEntity.id();
+
+    for child in children.unwrap_or_default() {
+        let childCopy = duplicateEntity(world, registry, child);
+        world.entity_mut(copy).add_child(childCopy);
+    }
+
+    return copy;
+}
+
+// Deep-clones an entity together with all of its descendants, placing the copy next to the original in the hierarchy
+pub fn duplicateHierarchy(world: &mut World, source: Entity) -> Entity {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let copy = duplicateEntity(world, &registry, source);
+
+    // Copies are told apart like in Blender, "Box" becomes "Box.001", the same goes for every entity under the copy
+    let mut taken = takenNames(world);
+    let mut pending = vec![copy];
+    while let Some(entity) = pending.pop() {
+        if let Some(children) = world.get::<Children>(entity) {
+            pending.extend(children.iter().copied());
+        }
+        let Some(name) = world.get::<Name>(entity).map(|name| name.as_str().to_string()) else { continue; };
+        let name = uniqueName(&taken, &name);
+        taken.insert(name.clone());
+        world.entity_mut(entity).insert(Name::new(name));
+    }
+    if let Some(parent) = world.get::<Parent>(source).map(|parent| parent.get()) {
+        world.entity_mut(parent).add_child(copy);
+    }
+
+    return copy;
+}
+
+// Duplicates every source and moves the selection (outline and gizmo) over to the copies.
+// Sources under another source are already copied along with it, so they aren't duplicated a second time
+pub fn duplicateSelection(world: &mut World, sources: &[Entity]) -> Vec<Entity> {
+    let topLevel = sources.iter().copied()
+        .filter(|source| world.get_entity(*source).is_ok())
+        .filter(|source| !sources.iter().any(|other| other != source && isAncestor(world, *other, *source)))
+        .collect::<Vec<Entity>>();
+    let copies = topLevel.iter()
+        .map(|source| duplicateHierarchy(world, *source))
+        .collect::<Vec<Entity>>();
+
+    for source in sources.iter() {
+        setSelected(world, *source, false);
+    }
+    for copy in copies.iter() {
+        setSelected(world, *copy, true);
+    }
+
+    return copies;
+}
+
+// Reverse of `duplicateSelection`, despawning the copies and handing the selection back to the sources
+pub fn removeDuplicates(world: &mut World, sources: &[Entity], copies: &[Entity]) {
+    for copy in copies.iter() {
+        if let Ok(copyEntity) = world.get_entity_mut(*copy) {
+            copyEntity.despawn_recursive();
+        }
+    }
+    for source in sources.iter() {
+        setSelected(world, *source, true);
+    }
+}
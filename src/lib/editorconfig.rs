@@ -9,6 +9,12 @@ pub struct EditorSelectionConfiguration {
     pub highlightColour: Color,
 }
 
+pub struct EditorAutosaveConfiguration {
+    pub interval: f32, // seconds between timed snapshots
+    pub historySteps: usize, // history steps after which a snapshot is taken regardless of the timer, 0 disables it
+    pub snapshotCount: usize, // size of the recovery snapshot ring
+}
+
 pub struct EditorTransformGizmosConfiguration {
     pub rotationVisible: bool,
     pub translationVisible: bool,
@@ -24,6 +30,7 @@ pub struct EditorTransformGizmosConfiguration {
 pub struct EditorConfiguration {
     pub camera: EditorCameraConfiguration,
     pub selection: EditorSelectionConfiguration,
+    pub autosave: EditorAutosaveConfiguration,
 }
 
 impl Default for EditorConfiguration {
@@ -35,7 +42,12 @@ impl Default for EditorConfiguration {
             selection: EditorSelectionConfiguration { 
                 selectionColour: Color::linear_rgb(0.2, 0.2, 0.9),
                 highlightColour: Color::linear_rgb(1., 1., 1.),
-            }
+            },
+            autosave: EditorAutosaveConfiguration {
+                interval: 60.,
+                historySteps: 20,
+                snapshotCount: 5,
+            },
         }
     }
 }
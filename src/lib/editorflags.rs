@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::{EditorHidden, EditorLocked};
+
+use super::{
+    layers::{EditorLayers, OnEditorLayer},
+    selection::setSelected,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorFlag {
+    Hidden,
+    Locked,
+}
+
+// Hidden and locked entities can't be clicked, so they're taken out of the selection as well.
+// Applied to all entities or none of them, so every entity is checked first
+pub fn setEditorFlag(world: &mut World, entities: &[Entity], flag: EditorFlag, value: bool) -> Result<(), String> {
+    for entity in entities {
+        world.get_entity(*entity).map_err(|e| e.to_string())?;
+    }
+
+    for entity in entities {
+        let mut entityMut = world.entity_mut(*entity);
+        match (flag, value) {
+            (EditorFlag::Hidden, true) => { entityMut.insert(EditorHidden); },
+            (EditorFlag::Hidden, false) => { entityMut.remove::<EditorHidden>(); },
+            (EditorFlag::Locked, true) => { entityMut.insert(EditorLocked); },
+            (EditorFlag::Locked, false) => { entityMut.remove::<EditorLocked>(); },
+        }
+        if value {
+            setSelected(world, *entity, false);
+        }
+    }
+
+    Ok(())
+}
+
+// Runs after Bevy's visibility checks, overriding the result for hidden entities, entities on hidden layers and their descendants, so `Visibility` itself is never touched
+pub fn hideEditorHidden(
+    layers: Res<EditorLayers>,
+    hidden: Query<Entity, With<EditorHidden>>,
+    layered: Query<(Entity, &OnEditorLayer)>,
+    children: Query<&Children>,
+    mut viewVisibilities: Query<&mut ViewVisibility>,
+) {
+    let onHiddenLayers = layered.iter().filter(|(_, layer)| !layers.isVisible(&layer.0)).map(|(entity, _)| entity);
+
+    for entity in hidden.iter().chain(onHiddenLayers) {
+        for hiddenEntity in std::iter::once(entity).chain(children.iter_descendants(entity)) {
+            if let Ok(mut viewVisibility) = viewVisibilities.get_mut(hiddenEntity) {
+                viewVisibility.set_if_neq(ViewVisibility::HIDDEN);
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{EditorHidden, EditorId, EditorLocked};
+
+use super::layers::OnEditorLayer;
+
+// Whether the frontend asked for the hierarchy, updates are only streamed after it got a snapshot to apply them to
+#[derive(Resource, Default)]
+pub struct HierarchyStream {
+    pub enabled: bool,
+}
+
+// Which icon the outliner shows for an entity
+pub fn iconHint(entity: EntityRef) -> &'static str {
+    if entity.contains::<Camera>() { "camera" }
+    else if entity.contains::<DirectionalLight>() { "directionalLight" }
+    else if entity.contains::<PointLight>() { "pointLight" }
+    else if entity.contains::<SpotLight>() { "spotLight" }
+    else if entity.contains::<Mesh3d>() { "mesh" }
+    else { "empty" }
+}
+
+pub fn hierarchyNode(entity: EntityRef) -> Value {
+    json!({
+        "entity": entity.id().to_bits(),
+        "name": entity.get::<Name>().map(|name| name.as_str()),
+        "parent": entity.get::<Parent>().map(|parent| parent.get().to_bits()),
+        "children": entity.get::<Children>().map_or(vec![], |children| children.iter().map(|child| child.to_bits()).collect()),
+        "icon": iconHint(entity),
+        "hidden": entity.contains::<EditorHidden>(),
+        "locked": entity.contains::<EditorLocked>(),
+        "layer": entity.get::<OnEditorLayer>().map(|layer| layer.0.as_str()),
+    })
+}
+
+// Every scene entity, i.e. everything that has an `EditorId`
+pub fn hierarchySnapshot(world: &World) -> Value {
+    let nodes = world.iter_entities()
+        .filter(|entity| entity.contains::<EditorId>())
+        .map(hierarchyNode)
+        .collect::<Vec<Value>>();
+
+    json!({ "type": "snapshot", "entities": nodes })
+}
+
+// Whether `ancestor` is `entity` or one of its parents, reparenting onto one of those would make a cycle
+pub fn isAncestor(world: &World, ancestor: Entity, entity: Entity) -> bool {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if entity == ancestor { return true; }
+        current = world.get::<Parent>(entity).map(|parent| parent.get());
+    }
+    return false;
+}
+
+// Moves an entity under a new parent (or to the root) with the given local transform
+pub fn setParent(world: &mut World, entity: Entity, parent: Option<Entity>, transform: Transform) -> Result<(), String> {
+    let mut entityMut = world.get_entity_mut(entity).map_err(|e| e.to_string())?;
+    match parent {
+        Some(parent) => { entityMut.set_parent(parent); },
+        None => { entityMut.remove_parent(); },
+    }
+    entityMut.insert(transform);
+    Ok(())
+}
+
+// Reparents an entity, returning its previous parent and local transform so the change can be undone, plus the new local transform.
+// Keeping the world transform recomputes the local one relative to the new parent, so the entity stays where it is on screen
+pub fn reparentEntity(world: &mut World, entity: Entity, parent: Option<Entity>, keepWorldTransform: bool) -> Result<(Option<Entity>, Transform, Transform), String> {
+    world.get_entity(entity).map_err(|e| e.to_string())?;
+    if let Some(parent) = parent {
+        world.get_entity(parent).map_err(|e| e.to_string())?;
+        if isAncestor(world, entity, parent) {
+            return Err("An entity can't be parented to itself or one of its descendants".to_string());
+        }
+    }
+
+    let previousParent = world.get::<Parent>(entity).map(|parent| parent.get());
+    let previousTransform = *world.get::<Transform>(entity).ok_or("Entity has no Transform")?;
+
+    let transform = match (keepWorldTransform, world.get::<GlobalTransform>(entity)) {
+        (true, Some(global)) => match parent.and_then(|parent| world.get::<GlobalTransform>(parent)) {
+            Some(parentGlobal) => global.reparented_to(parentGlobal),
+            None => global.compute_transform(),
+        },
+        _ => previousTransform,
+    };
+
+    setParent(world, entity, parent, transform)?;
+    Ok((previousParent, previousTransform, transform))
+}
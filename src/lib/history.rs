@@ -1,11 +1,59 @@
-use bevy::prelude::*;
-use transform_gizmo_bevy::{GizmoResult, GizmoTransform};
+use std::sync::Arc;
 
-use crate::wasm::definitions::consoleLog;
+use bevy::{prelude::*, reflect::PartialReflect};
+use serde_json::Value;
+use transform_gizmo_bevy::{GizmoResult, GizmoTransform};
 
 #[derive(Debug, Clone)]
 pub enum HistoryItem {
     Transform(GizmoTransform, Transform),
+    // Entities moved by the gizmo together, rotated and scaled around the world-space `pivot`, or each around its own origin if None
+    GroupTransform { targets: Vec<GizmoTransform>, pivot: Option<Vec3> },
+    // Entities that were duplicated and the resulting copies, in the same order
+    Duplicate { sources: Vec<Entity>, copies: Vec<Entity> },
+    // Property edits from the inspector, with the JSON values before and after the edit
+    Property { entity: Entity, componentName: String, property: String, before: Value, after: Value },
+    // Material edits, `previous` is the material the entity used before if the edit forked a unique copy
+    Material { entity: Entity, material: Handle<StandardMaterial>, previous: Option<Handle<StandardMaterial>>, property: String, before: Value, after: Value },
+    // Components added or removed from the inspector, `value` is what was inserted or what was there before the removal
+    Component { entity: Entity, componentName: String, value: Arc<dyn PartialReflect>, inserted: bool },
+    // Edits to resources shown as scene settings, e.g. the ambient light
+    Resource { resourceName: String, property: String, before: Value, after: Value },
+    // Entities moved in the hierarchy, with their parent and local transform before and after the move
+    Reparent { entity: Entity, before: (Option<Entity>, Transform), after: (Option<Entity>, Transform) },
+    // Several items undone and redone as one step, e.g. an edit applied to every selected entity
+    Batch(Vec<HistoryItem>),
+}
+
+// Redoes or undoes a gizmo transform of an entity that was moved as part of a group. Like the gizmo, this works in world space:
+// positions are rotated and scaled around the group's pivot and the result is turned back into a transform relative to the parent
+pub fn applyGroupTransform(transform: &mut Transform, parentGlobal: Option<&GlobalTransform>, result: &GizmoResult, pivot: Option<Vec3>, isUndo: bool) {
+    let direction = if isUndo { -1. } else { 1. };
+    let mut world = parentGlobal.map_or(*transform, |parentGlobal| parentGlobal.mul_transform(*transform).compute_transform());
+    let pivot = pivot.unwrap_or(world.translation);
+
+    match *result {
+        GizmoResult::Translation { delta: _, total } => {
+            world.translation += Vec3::new(total.x as f32, total.y as f32, total.z as f32) * direction;
+        },
+        GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
+            let rotation = Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), total as f32 * direction);
+            world.translation = pivot + rotation * (world.translation - pivot);
+            world.rotation = rotation * world.rotation;
+        },
+        GizmoResult::Scale { total } => {
+            let scale = Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+            let scale = if isUndo { 1. / scale } else { scale };
+            world.translation = pivot + (world.translation - pivot) * scale;
+            world.scale *= scale;
+        },
+        _ => {},
+    }
+
+    *transform = match parentGlobal {
+        Some(parentGlobal) => GlobalTransform::from(world).reparented_to(parentGlobal),
+        None => world,
+    };
 }
 
 pub enum HistoryAction {
@@ -27,54 +75,4 @@ impl History {
             action: HistoryAction::None, // the action that should happen next frame
         }
     }
-
-    pub fn undo(&mut self) {
-        if let Some(action) = self.past.pop() {
-            consoleLog("Undoing...");
-
-            match action {
-                HistoryItem::Transform(gizmoTransform, mut transform) => {
-                    match gizmoTransform.1 {
-                        GizmoResult::Translation { delta: _, total } => {
-                            consoleLog(&format!("Undoing translation from:\n{:?}", transform.translation));
-                            transform.translation -= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                            consoleLog(&format!("To:\n{:?}", transform.translation));
-                        },
-                        GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
-                            transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), -total as f32);
-                        },
-                        GizmoResult::Scale { total } => {
-                            transform.scale /= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                        },
-                        _ => {},
-                    };
-                },
-            };
-
-            self.future.push(action);
-        }
-    }
-
-    pub fn redo(&mut self) {
-        if let Some(action) = self.future.pop() {
-            match action {
-                HistoryItem::Transform(gizmoTransform, mut transform) => {
-                    match gizmoTransform.1 {
-                        GizmoResult::Translation { delta: _, total } => {
-                            transform.translation += Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                        },
-                        GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
-                            transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), total as f32);
-                        },
-                        GizmoResult::Scale { total } => {
-                            transform.scale *= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                        },
-                        _ => {},
-                    };
-                },
-            };
-
-            self.past.push(action);
-        }
-    }
 }
\ No newline at end of file
@@ -0,0 +1,314 @@
+use std::{any::TypeId, collections::{HashMap, HashSet}};
+
+use bevy::{
+    asset::ReflectHandle,
+    ecs::{component::Tick, entity::Entity, reflect::{AppTypeRegistry, ReflectComponent}, system::Resource, world::World},
+    reflect::{
+        DynamicArray,
+        DynamicEnum,
+        DynamicList,
+        DynamicMap,
+        DynamicSet,
+        DynamicStruct,
+        DynamicTuple,
+        DynamicTupleStruct,
+        DynamicVariant,
+        PartialReflect,
+        ReflectRef,
+        TypeInfo,
+        TypeRegistry,
+        VariantInfo,
+        VariantType,
+    },
+};
+use serde_json::{json, Map, Value};
+
+use super::{
+    editorvisibility::{isEditorHiddenType, EditorVisible},
+    properties::{defaultComponent, deserializeTyped, serializeValue},
+    registration::EditorComponents,
+};
+
+fn handleId(value: &dyn PartialReflect, registry: &TypeRegistry) -> Option<String> {
+    let reflectHandle = registry.get_type_data::<ReflectHandle>(value.get_represented_type_info()?.type_id())?;
+    let handle = reflectHandle.downcast_handle_untyped(value.try_as_reflect()?.as_any())?;
+
+    Some(handle.id().to_string())
+}
+
+fn isOption(value: &dyn PartialReflect) -> bool {
+    value.reflect_type_path().starts_with("core::option::Option<")
+}
+
+// Turns any reflected value into JSON for the inspector, `fromInspectorValue` turns it back. The shape mirrors the reflection paths `setProperty` takes:
+// structs are objects, tuple(-struct)s/lists/arrays/sets are arrays, maps are arrays of [key, value] pairs,
+// options are either null or their value, other enums are { variant, fields? } and asset handles are { id }
+pub fn inspectValue(value: &dyn PartialReflect, registry: &TypeRegistry) -> Value {
+    if let Some(id) = handleId(value, registry) {
+        return json!({ "id": id });
+    }
+
+    match value.reflect_ref() {
+        ReflectRef::Struct(structValue) => Value::Object(structValue.iter_fields().enumerate()
+            .map(|(i, field)| (structValue.name_at(i).unwrap_or_default().to_string(), inspectValue(field, registry)))
+            .collect()),
+        ReflectRef::TupleStruct(tupleStruct) => Value::Array(tupleStruct.iter_fields().map(|field| inspectValue(field, registry)).collect()),
+        ReflectRef::Tuple(tuple) => Value::Array(tuple.iter_fields().map(|field| inspectValue(field, registry)).collect()),
+        ReflectRef::List(list) => Value::Array(list.iter().map(|item| inspectValue(item, registry)).collect()),
+        ReflectRef::Array(array) => Value::Array(array.iter().map(|item| inspectValue(item, registry)).collect()),
+        ReflectRef::Set(set) => Value::Array(set.iter().map(|item| inspectValue(item, registry)).collect()),
+        ReflectRef::Map(map) => Value::Array(map.iter()
+            .map(|(key, item)| json!([inspectValue(key, registry), inspectValue(item, registry)]))
+            .collect()),
+        ReflectRef::Enum(enumValue) => {
+            if isOption(value) {
+                return enumValue.field_at(0).map_or(Value::Null, |inner| inspectValue(inner, registry));
+            }
+
+            let mut obj = Map::new();
+            obj.insert("variant".to_string(), enumValue.variant_name().into());
+            match enumValue.variant_type() {
+                VariantType::Struct => {
+                    obj.insert("fields".to_string(), Value::Object(enumValue.iter_fields()
+                        .map(|field| (field.name().unwrap_or_default().to_string(), inspectValue(field.value(), registry)))
+                        .collect()));
+                },
+                VariantType::Tuple => {
+                    obj.insert("fields".to_string(), Value::Array(enumValue.iter_fields().map(|field| inspectValue(field.value(), registry)).collect()));
+                },
+                VariantType::Unit => {},
+            }
+
+            Value::Object(obj)
+        },
+        // Primitives, strings and the like, anything without a serde representation is shown as its debug output
+        ReflectRef::Opaque(_) => serializeValue(value, registry).unwrap_or_else(|_| Value::String(format!("{:?}", value))),
+        #[allow(unreachable_patterns)]
+        _ => Value::Null,
+    }
+}
+
+fn inspectedField(typeInfo: Option<&'static TypeInfo>, value: &Value, registry: &TypeRegistry) -> Result<Box<dyn PartialReflect>, String> {
+    fromInspectorValue(typeInfo.ok_or("Field has no type information")?, value, registry)
+}
+
+fn inspectedItems(value: &Value, typePath: &str) -> Result<Vec<Value>, String> {
+    value.as_array().cloned().ok_or_else(|| format!("Expected an array for {}", typePath))
+}
+
+// The reverse of `inspectValue`, so anything the inspector sent can be written back as it is
+pub fn fromInspectorValue(typeInfo: &'static TypeInfo, value: &Value, registry: &TypeRegistry) -> Result<Box<dyn PartialReflect>, String> {
+    let typePath = typeInfo.type_path();
+    if registry.get_type_data::<ReflectHandle>(typeInfo.type_id()).is_some() {
+        return Err(format!("{} is an asset handle, which can't be set from its id", typePath));
+    }
+
+    match typeInfo {
+        TypeInfo::Struct(structInfo) => {
+            let fields = value.as_object().ok_or_else(|| format!("Expected an object for {}", typePath))?;
+            let mut dynamic = DynamicStruct::default();
+            for (name, field) in fields {
+                let fieldInfo = structInfo.field(name).ok_or_else(|| format!("{} has no field {}", typePath, name))?;
+                dynamic.insert_boxed(name.as_str(), inspectedField(fieldInfo.type_info(), field, registry)?);
+            }
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::TupleStruct(tupleInfo) => {
+            let items = inspectedItems(value, typePath)?;
+            if items.len() != tupleInfo.field_len() { return Err(format!("Expected {} items for {}", tupleInfo.field_len(), typePath)); }
+
+            let mut dynamic = tupleInfo.iter().zip(items.iter())
+                .map(|(field, item)| inspectedField(field.type_info(), item, registry))
+                .collect::<Result<DynamicTupleStruct, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::Tuple(tupleInfo) => {
+            let items = inspectedItems(value, typePath)?;
+            if items.len() != tupleInfo.field_len() { return Err(format!("Expected {} items for {}", tupleInfo.field_len(), typePath)); }
+
+            let mut dynamic = tupleInfo.iter().zip(items.iter())
+                .map(|(field, item)| inspectedField(field.type_info(), item, registry))
+                .collect::<Result<DynamicTuple, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::List(listInfo) => {
+            let mut dynamic = inspectedItems(value, typePath)?.iter()
+                .map(|item| inspectedField(listInfo.item_info(), item, registry))
+                .collect::<Result<DynamicList, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::Array(arrayInfo) => {
+            let items = inspectedItems(value, typePath)?;
+            if items.len() != arrayInfo.capacity() { return Err(format!("Expected {} items for {}", arrayInfo.capacity(), typePath)); }
+
+            let mut dynamic = items.iter()
+                .map(|item| inspectedField(arrayInfo.item_info(), item, registry))
+                .collect::<Result<DynamicArray, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::Set(setInfo) => {
+            let itemInfo = registry.get_type_info(setInfo.value_ty().id());
+            let mut dynamic = inspectedItems(value, typePath)?.iter()
+                .map(|item| inspectedField(itemInfo, item, registry))
+                .collect::<Result<DynamicSet, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::Map(mapInfo) => {
+            let mut dynamic = inspectedItems(value, typePath)?.iter()
+                .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                    Some([key, item]) => Ok((inspectedField(mapInfo.key_info(), key, registry)?, inspectedField(mapInfo.value_info(), item, registry)?)),
+                    _ => Err(format!("Expected [key, value] pairs for {}", typePath)),
+                })
+                .collect::<Result<DynamicMap, String>>()?;
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        TypeInfo::Enum(enumInfo) => {
+            // Options are either null or their value
+            let (variantName, fields) = if typePath.starts_with("core::option::Option<") {
+                match value {
+                    Value::Null => ("None", Value::Null),
+                    _ => ("Some", json!([value])),
+                }
+            } else {
+                let variantName = value.get("variant").and_then(Value::as_str).ok_or_else(|| format!("Expected {{ variant, fields? }} for {}", typePath))?;
+                (variantName, value.get("fields").cloned().unwrap_or(Value::Null))
+            };
+            let variantInfo = enumInfo.variant(variantName).ok_or_else(|| format!("{} has no variant {}", typePath, variantName))?;
+
+            let variant = match variantInfo {
+                VariantInfo::Struct(structInfo) => {
+                    let fields = fields.as_object().ok_or_else(|| format!("Expected an object of fields for {}::{}", typePath, variantName))?;
+                    let mut dynamic = DynamicStruct::default();
+                    for (name, field) in fields {
+                        let fieldInfo = structInfo.field(name).ok_or_else(|| format!("{}::{} has no field {}", typePath, variantName, name))?;
+                        dynamic.insert_boxed(name.as_str(), inspectedField(fieldInfo.type_info(), field, registry)?);
+                    }
+                    DynamicVariant::Struct(dynamic)
+                },
+                VariantInfo::Tuple(tupleInfo) => {
+                    let items = inspectedItems(&fields, typePath)?;
+                    if items.len() != tupleInfo.field_len() { return Err(format!("Expected {} fields for {}::{}", tupleInfo.field_len(), typePath, variantName)); }
+
+                    DynamicVariant::Tuple(tupleInfo.iter().zip(items.iter())
+                        .map(|(field, item)| inspectedField(field.type_info(), item, registry))
+                        .collect::<Result<DynamicTuple, String>>()?)
+                },
+                VariantInfo::Unit(_) => DynamicVariant::Unit,
+            };
+
+            let mut dynamic = DynamicEnum::new(variantName, variant);
+            dynamic.set_represented_type(Some(typeInfo));
+            Ok(Box::new(dynamic))
+        },
+        // Primitives and the like are shown in their serde shape already
+        TypeInfo::Opaque(_) => deserializeTyped(typeInfo, value, registry),
+    }
+}
+
+// Every component the frontend can add to an entity, which is any visible component with a default value
+pub fn insertableComponents(world: &mut World) -> Vec<Value> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut registrations = registry.iter()
+        .filter(|registration| registration.data::<ReflectComponent>().is_some() && !isEditorHiddenType(registration.type_id()))
+        .collect::<Vec<_>>();
+    registrations.sort_by_key(|registration| registration.type_info().type_path());
+
+    let mut out = vec![];
+    for registration in registrations {
+        let typeId = registration.type_id();
+        let Some(default) = defaultComponent(world, typeId) else { continue; };
+
+        let mut obj = Map::new();
+        obj.insert("name".to_string(), registration.type_info().type_path().into());
+        obj.insert("shortName".to_string(), registration.type_info().type_path_table().short_path().into());
+        obj.insert("default".to_string(), inspectValue(default.as_partial_reflect(), &registry));
+
+        if let Some(info) = world.get_resource::<EditorComponents>().and_then(|components| components.get(typeId)) {
+            for (key, value) in [("displayName", &info.displayName), ("category", &info.category), ("icon", &info.icon)] {
+                if let Some(value) = value {
+                    obj.insert(key.to_string(), value.as_str().into());
+                }
+            }
+        }
+
+        out.push(Value::Object(obj));
+    }
+
+    return out;
+}
+
+// What the inspector shows for a component, the game's own serializer takes precedence over reflection
+pub fn componentValue(world: &World, entity: Entity, typeId: TypeId) -> Option<Value> {
+    let component = world.get_reflect(entity, typeId).ok()?;
+
+    let customInfo = world.get_resource::<EditorComponents>()
+        .and_then(|components| components.get(typeId))
+        .and_then(|info| info.serializer.as_ref())
+        .and_then(|serializer| serializer(component));
+    if customInfo.is_some() {
+        return customInfo;
+    }
+
+    let registry = world.resource::<AppTypeRegistry>().read();
+    Some(inspectValue(component.as_partial_reflect(), &registry))
+}
+
+// Leaf-level differences between two inspector values, as { path, value } with the path being a list of object keys and array indices
+pub fn diffValues(before: &Value, after: &Value, path: &mut Vec<Value>, out: &mut Vec<Value>) {
+    match (before, after) {
+        (Value::Object(beforeFields), Value::Object(afterFields)) if beforeFields.len() == afterFields.len() && beforeFields.keys().all(|key| afterFields.contains_key(key)) => {
+            for (key, afterField) in afterFields {
+                path.push(key.as_str().into());
+                diffValues(&beforeFields[key], afterField, path, out);
+                path.pop();
+            }
+        },
+        (Value::Array(beforeItems), Value::Array(afterItems)) if beforeItems.len() == afterItems.len() => {
+            for (i, (beforeItem, afterItem)) in beforeItems.iter().zip(afterItems).enumerate() {
+                path.push(i.into());
+                diffValues(beforeItem, afterItem, path, out);
+                path.pop();
+            }
+        },
+        // Anything that changed shape (e.g. another enum variant) is sent whole
+        _ => if before != after {
+            out.push(json!({ "path": path.clone(), "value": after }));
+        },
+    }
+}
+
+pub struct WatchedEntity {
+    pub components: Option<HashSet<TypeId>>, // None watches every component the inspector shows
+    pub sent: HashMap<TypeId, Value>, // values the frontend last received, deltas are relative to these
+}
+
+// Entities the frontend is showing in the inspector, changes to them are pushed every frame
+#[derive(Resource, Default)]
+pub struct InspectorWatches {
+    pub entities: HashMap<Entity, WatchedEntity>,
+    pub lastCheck: Tick,
+}
+
+// Watched components currently on an entity, with their component names
+pub fn watchedComponents(world: &World, entity: Entity, components: &Option<HashSet<TypeId>>, registry: &TypeRegistry) -> Vec<(TypeId, String)> {
+    world.inspect_entity(entity)
+        .filter(|component| component.isEditorVisible(registry))
+        .filter_map(|component| Some((component.type_id()?, component.name().to_string())))
+        .filter(|(typeId, _)| components.as_ref().map_or(true, |components| components.contains(typeId)))
+        .collect()
+}
+
+pub fn watchBaseline(world: &World, entity: Entity, components: &Option<HashSet<TypeId>>, registry: &TypeRegistry) -> HashMap<TypeId, Value> {
+    watchedComponents(world, entity, components, registry).into_iter()
+        .filter_map(|(typeId, _)| Some((typeId, componentValue(world, entity, typeId)?)))
+        .collect()
+}
@@ -1,96 +1,251 @@
-use bevy::{asset::{Asset, Handle}, color::{Color, LinearRgba}, math::Vec2, prelude::*, reflect::Enum, sprite::{Anchor, TextureAtlas}};
-use js_sys::{JsString, Object, Reflect};
+use bevy::{asset::{Asset, AssetId, AssetIndex, Handle}, color::{Color, LinearRgba}, math::Vec2, prelude::*, reflect::Enum, sprite::{Anchor, TextureAtlas}};
+use js_sys::{JsString, Object, Reflect, JSON};
+use serde::Serialize;
 use wasm_bindgen::JsValue;
 
+// Setting a field only fails on frozen objects and proxies, which plain objects made here never are
+fn setField(obj: &Object, key: &str, value: &JsValue) {
+    let _ = Reflect::set(obj, &JsString::from(key), value);
+}
+
 pub fn asJsObject(values: Vec<(&str, JsValue)>) -> Object {
     let obj = Object::new();
     
     for (key, value) in values {
-        Reflect::set(&obj, &JsString::from(key), &value.into()).unwrap();
+        setField(&obj, key, &value);
     }
     
     return obj;
 }
 
+// For larger structures which already have a serde representation (saved scenes, diffs...)
+pub fn serdeAsJsObject<T: Serialize>(value: &T) -> Object {
+    serde_json::to_string(value).ok()
+        .and_then(|json| JSON::parse(&json).ok())
+        .map(Object::from)
+        .unwrap_or_else(Object::new)
+}
+
+// Entities are identified by their bits on the JS side, which fit into a JS number as long as the generation stays below 2^21
+pub fn entityIntoJs(entity: Entity) -> JsValue {
+    JsValue::from_f64(entity.to_bits() as f64)
+}
+
 pub trait IntoJs {
     fn intoJs(&self) -> Object;
 }
 
+// The reverse of `IntoJs`, taking the shapes `intoJs` produces. Most of the bridge passes JSON strings around,
+// this is for exported functions taking JS objects straight from the frontend, like `Runner::setTransformProperty`
+pub trait FromJs: Sized {
+    fn fromJs(value: &JsValue) -> Result<Self, String>;
+}
+
+fn getField(value: &JsValue, key: &str) -> Result<JsValue, String> {
+    if !value.is_object() {
+        return Err(format!("Expected an object with a \"{}\" field, got {:?}", key, value));
+    }
+
+    Reflect::get(value, &JsString::from(key)).map_err(|_| format!("Failed to read field \"{}\"", key))
+}
+
+fn getNumber(value: &JsValue, key: &str) -> Result<f32, String> {
+    let field = getField(value, key)?;
+    field.as_f64()
+        .filter(|number| number.is_finite())
+        .map(|number| number as f32)
+        .ok_or_else(|| format!("Field \"{}\" should be a finite number, got {:?}", key, field))
+}
+
+fn getString(value: &JsValue, key: &str) -> Result<String, String> {
+    let field = getField(value, key)?;
+    field.as_string().ok_or_else(|| format!("Field \"{}\" should be a string, got {:?}", key, field))
+}
+
+fn getInteger(value: &JsValue, key: &str) -> Result<u32, String> {
+    let field = getField(value, key)?;
+    field.as_f64()
+        .filter(|number| number.fract() == 0. && (0. ..=u32::MAX as f64).contains(number))
+        .map(|number| number as u32)
+        .ok_or_else(|| format!("Field \"{}\" should be a positive integer, got {:?}", key, field))
+}
+
+// Asset ids are sent as `{ index, generation }` or `{ uuid }`, as the display output of `AssetId` isn't meant to be parsed
+fn assetIdIntoJs<T: Asset>(id: AssetId<T>) -> Object {
+    let obj = Object::new();
+    match id {
+        AssetId::Index { index, .. } => {
+            let bits = index.to_bits();
+            setField(&obj, "index", &JsValue::from(bits as u32));
+            setField(&obj, "generation", &JsValue::from((bits >> 32) as u32));
+        },
+        AssetId::Uuid { uuid } => setField(&obj, "uuid", &JsValue::from(uuid.to_string())),
+    }
+
+    return obj;
+}
+
+fn assetIdFromJs<T: Asset>(value: &JsValue) -> Result<AssetId<T>, String> {
+    if !getField(value, "uuid")?.is_undefined() {
+        // `Uuid` is only reachable through serde, as bevy doesn't re-export the crate
+        let uuid = getString(value, "uuid")?;
+        return serde_json::from_value(serde_json::json!({ "Uuid": { "uuid": uuid } })).map_err(|e| format!("{} is not a valid asset uuid: {}", uuid, e));
+    }
+
+    let (index, generation) = (getInteger(value, "index")?, getInteger(value, "generation")?);
+    Ok(AssetId::from(AssetIndex::from_bits(((generation as u64) << 32) | index as u64)))
+}
+
 impl IntoJs for Color {
     fn intoJs(&self) -> Object {
         let colour = LinearRgba::from(*self);
         let colourObj = Object::new();
 
-        Reflect::set(&colourObj, &JsString::from("r"), &JsValue::from_f64(colour.red as f64)).unwrap();
-        Reflect::set(&colourObj, &JsString::from("g"), &JsValue::from_f64(colour.green as f64)).unwrap();
-        Reflect::set(&colourObj, &JsString::from("b"), &JsValue::from_f64(colour.blue as f64)).unwrap();
-        Reflect::set(&colourObj, &JsString::from("a"), &JsValue::from_f64(colour.alpha as f64)).unwrap();
+        setField(&colourObj, "r", &JsValue::from_f64(colour.red as f64));
+        setField(&colourObj, "g", &JsValue::from_f64(colour.green as f64));
+        setField(&colourObj, "b", &JsValue::from_f64(colour.blue as f64));
+        setField(&colourObj, "a", &JsValue::from_f64(colour.alpha as f64));
 
         return colourObj;
     }
 }
 
+// Colours are read as linear, like `intoJs` sends them, so any other colour space comes back as its linear equivalent. Alpha can be left out
+impl FromJs for Color {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        let alpha = if getField(value, "a")?.is_undefined() { 1. } else { getNumber(value, "a")? };
+
+        Ok(Color::LinearRgba(LinearRgba::new(getNumber(value, "r")?, getNumber(value, "g")?, getNumber(value, "b")?, alpha)))
+    }
+}
+
 impl IntoJs for TextureAtlas {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("atlasLayout"), &JsValue::from(self.layout.id().to_string())).unwrap();
-        Reflect::set(&obj, &JsString::from("textureAtlasId"), &JsValue::from(self.index)).unwrap();
+        setField(&obj, "atlasLayout", &assetIdIntoJs(self.layout.id()));
+        setField(&obj, "textureAtlasId", &JsValue::from(self.index));
         return obj;
     }
 }
 
+impl FromJs for TextureAtlas {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        let index = getNumber(value, "textureAtlasId")?;
+        if index < 0. || index.fract() != 0. {
+            return Err(format!("Field \"textureAtlasId\" should be a positive integer, got {}", index));
+        }
+
+        Ok(TextureAtlas {
+            layout: Handle::Weak(assetIdFromJs(&getField(value, "atlasLayout")?).map_err(|e| format!("In \"atlasLayout\": {}", e))?),
+            index: index as usize,
+        })
+    }
+}
+
 impl IntoJs for Vec2 {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("x"), &JsValue::from(self.x)).unwrap();
-        Reflect::set(&obj, &JsString::from("y"), &JsValue::from(self.y)).unwrap();
+        setField(&obj, "x", &JsValue::from(self.x));
+        setField(&obj, "y", &JsValue::from(self.y));
         return obj;
     }
 }
 
+impl FromJs for Vec2 {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        Ok(Vec2::new(getNumber(value, "x")?, getNumber(value, "y")?))
+    }
+}
+
 impl IntoJs for Vec3 {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("x"), &JsValue::from(self.x)).unwrap();
-        Reflect::set(&obj, &JsString::from("y"), &JsValue::from(self.y)).unwrap();
-        Reflect::set(&obj, &JsString::from("z"), &JsValue::from(self.z)).unwrap();
+        setField(&obj, "x", &JsValue::from(self.x));
+        setField(&obj, "y", &JsValue::from(self.y));
+        setField(&obj, "z", &JsValue::from(self.z));
         return obj;
     }
 }
 
+impl FromJs for Vec3 {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        Ok(Vec3::new(getNumber(value, "x")?, getNumber(value, "y")?, getNumber(value, "z")?))
+    }
+}
+
 impl IntoJs for Quat {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("x"), &JsValue::from(self.x)).unwrap();
-        Reflect::set(&obj, &JsString::from("y"), &JsValue::from(self.y)).unwrap();
-        Reflect::set(&obj, &JsString::from("z"), &JsValue::from(self.z)).unwrap();
-        Reflect::set(&obj, &JsString::from("w"), &JsValue::from(self.w)).unwrap();
+        setField(&obj, "x", &JsValue::from(self.x));
+        setField(&obj, "y", &JsValue::from(self.y));
+        setField(&obj, "z", &JsValue::from(self.z));
+        setField(&obj, "w", &JsValue::from(self.w));
         return obj;
     }
 }
 
+impl FromJs for Quat {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        let quat = Quat::from_xyzw(getNumber(value, "x")?, getNumber(value, "y")?, getNumber(value, "z")?, getNumber(value, "w")?);
+        if quat.length_squared() == 0. {
+            return Err("A rotation can't have a length of 0".to_string());
+        }
+
+        Ok(quat)
+    }
+}
+
 impl IntoJs for Rect {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("min"), &self.min.intoJs()).unwrap();
-        Reflect::set(&obj, &JsString::from("max"), &self.max.intoJs()).unwrap();
+        setField(&obj, "min", &self.min.intoJs());
+        setField(&obj, "max", &self.max.intoJs());
         return obj;
     }
 }
 
+impl FromJs for Rect {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        let min = Vec2::fromJs(&getField(value, "min")?).map_err(|e| format!("In \"min\": {}", e))?;
+        let max = Vec2::fromJs(&getField(value, "max")?).map_err(|e| format!("In \"max\": {}", e))?;
+
+        Ok(Rect { min, max })
+    }
+}
+
 impl IntoJs for Anchor {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("type"), &JsValue::from(self.variant_name())).unwrap();
-        Reflect::set(&obj, &JsString::from("position"), &format!("{:?}", self).into()).unwrap();
+        setField(&obj, "type", &JsValue::from(self.variant_name()));
+        setField(&obj, "position", &JsValue::from(format!("{:?}", self)));
+        setField(&obj, "offset", &self.as_vec().intoJs());
         return obj;
     }
 }
 
+// Only custom anchors read their offset, the others are fixed
+impl FromJs for Anchor {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        match getString(value, "type")?.as_str() {
+            "Center" => Ok(Anchor::Center),
+            "BottomLeft" => Ok(Anchor::BottomLeft),
+            "BottomCenter" => Ok(Anchor::BottomCenter),
+            "BottomRight" => Ok(Anchor::BottomRight),
+            "CenterLeft" => Ok(Anchor::CenterLeft),
+            "CenterRight" => Ok(Anchor::CenterRight),
+            "TopLeft" => Ok(Anchor::TopLeft),
+            "TopCenter" => Ok(Anchor::TopCenter),
+            "TopRight" => Ok(Anchor::TopRight),
+            "Custom" => Ok(Anchor::Custom(Vec2::fromJs(&getField(value, "offset")?).map_err(|e| format!("In \"offset\": {}", e))?)),
+            other => Err(format!("{} is not an anchor", other)),
+        }
+    }
+}
+
 impl IntoJs for SpriteImageMode {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("type"), &JsValue::from(self.variant_name())).unwrap();
+        setField(&obj, "type", &JsValue::from(self.variant_name()));
         return obj;
     }
 }
@@ -98,31 +253,143 @@ impl IntoJs for SpriteImageMode {
 impl IntoJs for Visibility {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("visible"), &JsValue::from(self.variant_name())).unwrap();
+        setField(&obj, "visible", &JsValue::from(self.variant_name()));
         return obj;
     }
 }
 
-
-
-
+impl FromJs for Visibility {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        match getString(value, "visible")?.as_str() {
+            "Inherited" => Ok(Visibility::Inherited),
+            "Hidden" => Ok(Visibility::Hidden),
+            "Visible" => Ok(Visibility::Visible),
+            other => Err(format!("{} is not a visibility, expected Inherited, Hidden or Visible", other)),
+        }
+    }
+}
 
 impl<T: Asset> IntoJs for Handle<T> {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
-        Reflect::set(&obj, &JsString::from("id"), &JsValue::from(self.id().to_string())).unwrap();
+        setField(&obj, "id", &assetIdIntoJs(self.id()));
         return obj;
     }
 }
 
+// Handles made from JS are weak, the asset has to be kept alive elsewhere (e.g. `LoadedImages`)
+impl<T: Asset> FromJs for Handle<T> {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        Ok(Handle::Weak(assetIdFromJs(&getField(value, "id")?).map_err(|e| format!("In \"id\": {}", e))?))
+    }
+}
+
 impl<T: IntoJs> IntoJs for Option<T> {
     fn intoJs(&self) -> Object {
         let obj = Object::new();
         match self {
-            Some(value) => Reflect::set(&obj, &JsString::from("value"), &value.intoJs()).unwrap(),
-            None => Reflect::set(&obj, &JsString::from("value"), &JsValue::NULL).unwrap(),
+            Some(value) => setField(&obj, "value", &value.intoJs()),
+            None => setField(&obj, "value", &JsValue::NULL),
         };
 
         return obj;
     }
 }
+
+impl<T: FromJs> FromJs for Option<T> {
+    fn fromJs(value: &JsValue) -> Result<Self, String> {
+        let inner = getField(value, "value")?;
+        if inner.is_null() || inner.is_undefined() {
+            return Ok(None);
+        }
+
+        T::fromJs(&inner).map(Some)
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    fn roundTrip<T: IntoJs + FromJs + PartialEq + std::fmt::Debug>(value: T) {
+        let converted = T::fromJs(&value.intoJs().into());
+        assert_eq!(converted, Ok(value));
+    }
+
+    fn parse(json: &str) -> JsValue {
+        JSON::parse(json).unwrap()
+    }
+
+    fn fromJsError<T: FromJs + std::fmt::Debug>(json: &str) -> String {
+        T::fromJs(&parse(json)).unwrap_err()
+    }
+
+    #[wasm_bindgen_test]
+    fn colourRoundTrip() {
+        roundTrip(Color::LinearRgba(LinearRgba::new(0.25, 0.5, 1., 0.75)));
+        roundTrip(Color::LinearRgba(LinearRgba::BLACK));
+    }
+
+    #[wasm_bindgen_test]
+    fn vectorRoundTrip() {
+        roundTrip(Vec2::new(1.5, -2.));
+        roundTrip(Vec3::new(1.5, -2., 300.));
+        roundTrip(Quat::from_rotation_y(1.));
+        roundTrip(Rect::new(-1., -2., 3., 4.));
+    }
+
+    #[wasm_bindgen_test]
+    fn anchorRoundTrip() {
+        for anchor in [Anchor::Center, Anchor::BottomLeft, Anchor::TopRight, Anchor::CenterRight, Anchor::Custom(Vec2::new(0.25, -0.5))] {
+            roundTrip(anchor);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn visibilityRoundTrip() {
+        for visibility in [Visibility::Inherited, Visibility::Hidden, Visibility::Visible] {
+            roundTrip(visibility);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn optionRoundTrip() {
+        roundTrip(Some(Vec2::new(1., 2.)));
+        roundTrip(None::<Vec2>);
+    }
+
+    #[wasm_bindgen_test]
+    fn handleRoundTrip() {
+        roundTrip(Handle::<Image>::Weak(AssetId::from(AssetIndex::from_bits((3 << 32) | 17))));
+        roundTrip(Handle::<Image>::Weak(AssetId::default()));
+    }
+
+    #[wasm_bindgen_test]
+    fn colourComesBackLinear() {
+        let colour = Color::srgba(0.25, 0.5, 1., 0.75);
+        assert_eq!(Color::fromJs(&colour.intoJs().into()), Ok(Color::LinearRgba(colour.to_linear())));
+    }
+
+    #[wasm_bindgen_test]
+    fn colourAlphaDefaultsToOpaque() {
+        assert_eq!(Color::fromJs(&parse(r#"{ "r": 1, "g": 0, "b": 0 }"#)), Ok(Color::LinearRgba(LinearRgba::RED)));
+    }
+
+    #[wasm_bindgen_test]
+    fn badlyShapedInput() {
+        assert_eq!(fromJsError::<Vec2>("5"), "Expected an object with a \"x\" field, got JsValue(5)");
+        assert_eq!(fromJsError::<Vec3>(r#"{ "x": 1, "y": 2 }"#), "Field \"z\" should be a finite number, got JsValue(undefined)");
+        assert_eq!(fromJsError::<Vec2>(r#"{ "x": "1", "y": 2 }"#), "Field \"x\" should be a finite number, got JsValue(\"1\")");
+        assert_eq!(fromJsError::<Quat>(r#"{ "x": 0, "y": 0, "z": 0, "w": 0 }"#), "A rotation can't have a length of 0");
+        assert_eq!(fromJsError::<Rect>(r#"{ "min": { "x": 0, "y": 0 }, "max": { "x": 1 } }"#), "In \"max\": Field \"y\" should be a finite number, got JsValue(undefined)");
+        assert_eq!(fromJsError::<Anchor>(r#"{ "type": "Middle" }"#), "Middle is not an anchor");
+        assert_eq!(fromJsError::<Anchor>(r#"{ "type": "Custom" }"#), "In \"offset\": Expected an object with a \"x\" field, got JsValue(undefined)");
+        assert_eq!(fromJsError::<Visibility>(r#"{ "visible": true }"#), "Field \"visible\" should be a string, got JsValue(true)");
+        assert_eq!(fromJsError::<Visibility>(r#"{ "visible": "Shown" }"#), "Shown is not a visibility, expected Inherited, Hidden or Visible");
+        assert_eq!(fromJsError::<Option<Vec2>>(r#"{ "value": { "x": 1 } }"#), "Field \"y\" should be a finite number, got JsValue(undefined)");
+        assert_eq!(fromJsError::<Handle<Image>>(r#"{ "id": "not an id" }"#), "In \"id\": Expected an object with a \"uuid\" field, got JsValue(\"not an id\")");
+        assert_eq!(fromJsError::<Handle<Image>>(r#"{ "id": { "index": 1.5, "generation": 0 } }"#), "In \"id\": Field \"index\" should be a positive integer, got JsValue(1.5)");
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use serde_derive::{Deserialize, Serialize};
+
+use super::selection::setSelected;
+
+// Named group of entities in the editor, e.g. "Lighting" or "Collision", which can be hidden or made unpickable all at once
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EditorLayer {
+    pub name: String,
+    pub visible: bool,
+    pub selectable: bool,
+    pub colour: [f32; 4], // linear RGBA tag shown next to the layer's entities in the outliner
+}
+
+impl EditorLayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            selectable: true,
+            colour: [1., 1., 1., 1.],
+        }
+    }
+}
+
+// Layers of the current scene, saved along with it
+#[derive(Resource, Default)]
+pub struct EditorLayers {
+    pub layers: Vec<EditorLayer>,
+}
+
+impl EditorLayers {
+    pub fn get(&self, name: &str) -> Option<&EditorLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn isVisible(&self, name: &str) -> bool {
+        self.get(name).map_or(true, |layer| layer.visible)
+    }
+
+    pub fn isSelectable(&self, name: &str) -> bool {
+        self.get(name).map_or(true, |layer| layer.selectable)
+    }
+}
+
+// Which editor layer an entity is on, entities on a layer that doesn't exist act as if they're on none
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct OnEditorLayer(pub String);
+
+// Partial update of a layer, creating it if there's no layer with that name yet
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LayerUpdate {
+    pub name: String,
+    pub visible: Option<bool>,
+    pub selectable: Option<bool>,
+    pub colour: Option<[f32; 4]>,
+}
+
+pub fn updateLayer(world: &mut World, update: &LayerUpdate) {
+    let mut layers = world.resource_mut::<EditorLayers>();
+    let index = match layers.layers.iter().position(|layer| layer.name == update.name) {
+        Some(index) => index,
+        None => {
+            layers.layers.push(EditorLayer::new(&update.name));
+            layers.layers.len() - 1
+        },
+    };
+
+    let layer = &mut layers.layers[index];
+    if let Some(visible) = update.visible { layer.visible = visible; }
+    if let Some(selectable) = update.selectable { layer.selectable = selectable; }
+    if let Some(colour) = update.colour { layer.colour = colour; }
+
+    // Entities that can't be clicked anymore are taken out of the selection, like hidden and locked ones
+    let clickable = layer.visible && layer.selectable;
+    if !clickable {
+        for entity in layerMembers(world, &update.name) {
+            setSelected(world, entity, false);
+        }
+    }
+}
+
+fn layerMembers(world: &mut World, name: &str) -> Vec<Entity> {
+    world.query::<(Entity, &OnEditorLayer)>().iter(world)
+        .filter(|(_, layer)| layer.0 == name)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+// Removing a layer moves its entities off of it
+pub fn removeLayer(world: &mut World, name: &str) -> Result<(), String> {
+    let mut layers = world.resource_mut::<EditorLayers>();
+    let index = layers.layers.iter().position(|layer| layer.name == name).ok_or_else(|| format!("There's no layer called {}", name))?;
+    layers.layers.remove(index);
+
+    for entity in layerMembers(world, name) {
+        world.entity_mut(entity).remove::<OnEditorLayer>();
+    }
+
+    Ok(())
+}
+
+// Moves entities onto a layer, or off of any layer with None
+pub fn assignLayer(world: &mut World, entities: &[Entity], layer: Option<&str>) -> Result<(), String> {
+    if let Some(layer) = layer {
+        world.resource::<EditorLayers>().get(layer).ok_or_else(|| format!("There's no layer called {}", layer))?;
+    }
+    for entity in entities {
+        world.get_entity(*entity).map_err(|e| e.to_string())?;
+    }
+
+    let selectable = isOnSelectableLayer(world.resource::<EditorLayers>(), layer.map(|layer| OnEditorLayer(layer.to_string())).as_ref());
+    for entity in entities {
+        match layer {
+            Some(layer) => { world.entity_mut(*entity).insert(OnEditorLayer(layer.to_string())); },
+            None => { world.entity_mut(*entity).remove::<OnEditorLayer>(); },
+        }
+        if !selectable {
+            setSelected(world, *entity, false);
+        }
+    }
+
+    Ok(())
+}
+
+// Whether an entity can be picked in the viewport, as far as its layer is concerned
+pub fn isOnSelectableLayer(layers: &EditorLayers, layer: Option<&OnEditorLayer>) -> bool {
+    layer.map_or(true, |layer| layers.isVisible(&layer.0) && layers.isSelectable(&layer.0))
+}
@@ -0,0 +1,36 @@
+use bevy::{prelude::*, render::primitives::Aabb};
+
+use super::pivot::aabbCorners;
+
+// Dragging further than this turns a click into a box selection, in logical pixels
+pub const MarqueeThreshold: f32 = 4.;
+
+// State of a box selection being dragged in the viewport
+#[derive(Resource, Default)]
+pub struct Marquee {
+    pub start: Option<Vec2>, // where the left button was pressed, None when it wasn't pressed in the viewport
+    pub current: Vec2,
+    pub active: bool, // whether the drag went past `MarqueeThreshold`
+}
+
+impl Marquee {
+    pub fn rect(&self) -> Option<Rect> {
+        self.start.filter(|_| self.active).map(|start| Rect::from_corners(start, self.current))
+    }
+}
+
+// UI node drawing the selection rectangle
+#[derive(Component)]
+pub struct MarqueeOverlay;
+
+// Screen-space rectangle around an entity's bounding box, None if part of it is behind the camera
+pub fn screenBounds(camera: &Camera, cameraTransform: &GlobalTransform, aabb: &Aabb, transform: &GlobalTransform) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+    for corner in aabbCorners(aabb, transform) {
+        let point = camera.world_to_viewport(cameraTransform, corner).ok()?;
+
+        bounds = Some(bounds.map_or(Rect::from_corners(point, point), |bounds| bounds.union_point(point)));
+    }
+
+    return bounds;
+}
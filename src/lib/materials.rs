@@ -0,0 +1,95 @@
+use bevy::{
+    prelude::*,
+    reflect::{ReflectPath, Typed},
+};
+use serde_json::{json, Value};
+
+use super::{
+    inspector::inspectValue,
+    metadata::typeFieldMetadata,
+    properties::writeReflectPath,
+};
+
+pub fn entityMaterial(world: &World, entity: Entity) -> Result<Handle<StandardMaterial>, String> {
+    world.get::<MeshMaterial3d<StandardMaterial>>(entity)
+        .map(|material| material.0.clone())
+        .ok_or_else(|| "Entity has no standard material".to_string())
+}
+
+// How many entities a material is shared between, editing a shared material changes all of them
+pub fn materialUsers(world: &World, material: &Handle<StandardMaterial>) -> usize {
+    world.iter_entities()
+        .filter(|entity| entity.get::<MeshMaterial3d<StandardMaterial>>().is_some_and(|used| used.0 == *material))
+        .count()
+}
+
+// Inspector info for the material of an entity, `properties` has the same shape as the info of components
+pub fn materialInfo(world: &World, entity: Entity) -> Option<Value> {
+    let handle = entityMaterial(world, entity).ok()?;
+    let material = world.resource::<Assets<StandardMaterial>>().get(&handle)?;
+    let registry = world.resource::<AppTypeRegistry>().read();
+
+    Some(json!({
+        "id": handle.id().to_string(),
+        "users": materialUsers(world, &handle),
+        "properties": inspectValue(material.as_partial_reflect(), &registry),
+        "metadata": typeFieldMetadata(StandardMaterial::type_info(), None),
+    }))
+}
+
+fn textureValue(texture: &Option<Handle<Image>>) -> Value {
+    texture.as_ref().map_or(Value::Null, |handle| json!({ "id": handle.id().to_string() }))
+}
+
+// Texture slots take the id of a loaded image (as in the inspector output, or just the id string), or null to clear them
+fn resolveTexture(world: &mut World, value: &Value) -> Result<Option<Handle<Image>>, String> {
+    if value.is_null() { return Ok(None); }
+
+    let id = value.get("id").and_then(Value::as_str)
+        .or(value.as_str())
+        .ok_or("Expected an image id or null")?;
+
+    let mut images = world.resource_mut::<Assets<Image>>();
+    let imageId = images.ids().find(|imageId| imageId.to_string() == id).ok_or_else(|| format!("No image with id {}", id))?;
+
+    images.get_strong_handle(imageId).map(Some).ok_or_else(|| format!("Image {} isn't loaded anymore", id))
+}
+
+// Writes a JSON value into a material field through reflection, returning the value that was there before
+pub fn writeMaterialProperty(world: &mut World, handle: &Handle<StandardMaterial>, property: &str, value: &Value) -> Result<Value, String> {
+    let isTextureSlot = {
+        let material = world.resource::<Assets<StandardMaterial>>().get(handle).ok_or("Material doesn't exist")?;
+        let field = property.reflect_element(material.as_partial_reflect()).map_err(|e| format!("Invalid property {}: {}", property, e))?;
+        field.try_downcast_ref::<Option<Handle<Image>>>().is_some()
+    };
+    // Handles can't be deserialized, so texture slots are resolved against the loaded images instead
+    let texture = if isTextureSlot { Some(resolveTexture(world, value)?) } else { None };
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+    let material = materials.get_mut(handle).ok_or("Material doesn't exist")?;
+
+    match texture {
+        Some(texture) => {
+            let field = property.reflect_element_mut(material.as_partial_reflect_mut()).map_err(|e| format!("Invalid property {}: {}", property, e))?;
+            let slot = field.try_downcast_mut::<Option<Handle<Image>>>().ok_or("Expected a texture slot")?;
+
+            Ok(textureValue(&std::mem::replace(slot, texture)))
+        },
+        None => writeReflectPath(material.as_partial_reflect_mut(), property, value, &registry),
+    }
+}
+
+// Gives an entity its own copy of its material, returning the handle it had before
+pub fn forkMaterial(world: &mut World, entity: Entity) -> Result<Handle<StandardMaterial>, String> {
+    let previous = entityMaterial(world, entity)?;
+
+    let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+    let copy = materials.get(&previous).cloned().ok_or("Material doesn't exist")?;
+    let forked = materials.add(copy);
+
+    world.entity_mut(entity).insert(MeshMaterial3d(forked));
+    Ok(previous)
+}
@@ -0,0 +1,241 @@
+use std::{any::TypeId, collections::{BTreeMap, HashMap}, f64::consts::PI, ops::RangeInclusive};
+
+use bevy::{
+    pbr::{DirectionalLight, PointLight, SpotLight, StandardMaterial},
+    prelude::*,
+    reflect::{NamedField, TypeInfo, Typed, UnnamedField, VariantInfo},
+    render::camera::PerspectiveProjection,
+};
+use once_cell::sync::Lazy;
+use serde_derive::Serialize;
+
+use super::registration::EditorComponentInfo;
+
+// Attributes for fields of game components, e.g. `#[reflect(@EditorRange { min: 0., max: 1., step: Some(0.01) })]`.
+// Bevy's own `#[reflect(@0.0..=1.0)]` style ranges are understood as well
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct EditorRange {
+    pub min: f64,
+    pub max: f64,
+    pub step: Option<f64>,
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct EditorUnit(pub &'static str);
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct EditorReadOnly;
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct FieldMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub readOnly: bool,
+    // All variants of an enum field, so it can be shown as a dropdown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<String>>,
+}
+
+impl FieldMetadata {
+    pub fn ranged(min: f64, max: f64, step: f64) -> Self {
+        Self {
+            min: Some(min),
+            max: Some(max),
+            step: Some(step),
+            ..default()
+        }
+    }
+
+    pub fn withMin(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn withUnit(mut self, unit: &str) -> Self {
+        self.unit = Some(unit.to_string());
+        self
+    }
+
+    pub fn readOnly() -> Self {
+        Self {
+            readOnly: true,
+            ..default()
+        }
+    }
+
+    pub fn isEmpty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Values set in `other` take precedence
+    pub fn merge(&mut self, other: &FieldMetadata) {
+        self.min = other.min.or(self.min);
+        self.max = other.max.or(self.max);
+        self.step = other.step.or(self.step);
+        self.unit = other.unit.clone().or(self.unit.take());
+        self.tooltip = other.tooltip.clone().or(self.tooltip.take());
+        self.readOnly |= other.readOnly;
+        self.variants = other.variants.clone().or(self.variants.take());
+    }
+}
+
+// Metadata for engine types, which can't be given attributes. Keyed by the type owning the field and the field name, an empty name refers to the type itself
+static BuiltinMetadata: Lazy<HashMap<(TypeId, &'static str), FieldMetadata>> = Lazy::new(|| {
+    let mut map = HashMap::new();
+
+    map.insert((TypeId::of::<DirectionalLight>(), "illuminance"), FieldMetadata::default().withMin(0.).withUnit("lx"));
+    map.insert((TypeId::of::<DirectionalLight>(), "shadow_depth_bias"), FieldMetadata::default().withMin(0.));
+    map.insert((TypeId::of::<DirectionalLight>(), "shadow_normal_bias"), FieldMetadata::default().withMin(0.));
+
+    map.insert((TypeId::of::<PointLight>(), "intensity"), FieldMetadata::default().withMin(0.).withUnit("lm"));
+    map.insert((TypeId::of::<PointLight>(), "range"), FieldMetadata::default().withMin(0.).withUnit("m"));
+    map.insert((TypeId::of::<PointLight>(), "radius"), FieldMetadata::default().withMin(0.).withUnit("m"));
+
+    map.insert((TypeId::of::<SpotLight>(), "intensity"), FieldMetadata::default().withMin(0.).withUnit("lm"));
+    map.insert((TypeId::of::<SpotLight>(), "range"), FieldMetadata::default().withMin(0.).withUnit("m"));
+    map.insert((TypeId::of::<SpotLight>(), "radius"), FieldMetadata::default().withMin(0.).withUnit("m"));
+    map.insert((TypeId::of::<SpotLight>(), "inner_angle"), FieldMetadata::ranged(0., PI / 2., 0.01).withUnit("rad"));
+    map.insert((TypeId::of::<SpotLight>(), "outer_angle"), FieldMetadata::ranged(0., PI / 2., 0.01).withUnit("rad"));
+
+    map.insert((TypeId::of::<PerspectiveProjection>(), "fov"), FieldMetadata::ranged(0.01, PI - 0.01, 0.01).withUnit("rad"));
+    map.insert((TypeId::of::<PerspectiveProjection>(), "near"), FieldMetadata::default().withMin(0.).withUnit("m"));
+    map.insert((TypeId::of::<PerspectiveProjection>(), "far"), FieldMetadata::default().withMin(0.).withUnit("m"));
+
+    map.insert((TypeId::of::<StandardMaterial>(), "perceptual_roughness"), FieldMetadata::ranged(0.089, 1., 0.01));
+    map.insert((TypeId::of::<StandardMaterial>(), "metallic"), FieldMetadata::ranged(0., 1., 0.01));
+    map.insert((TypeId::of::<StandardMaterial>(), "reflectance"), FieldMetadata::ranged(0., 1., 0.01));
+
+    // Computed by the engine every frame, editing them would be overwritten right away
+    map.insert((TypeId::of::<InheritedVisibility>(), ""), FieldMetadata::readOnly());
+    map.insert((TypeId::of::<ViewVisibility>(), ""), FieldMetadata::readOnly());
+
+    map
+});
+
+// Types the inspector shows in the shape of another type, e.g. global transforms are shown (and edited) as translation, rotation and scale
+static EditedAsTypes: Lazy<HashMap<TypeId, &'static TypeInfo>> = Lazy::new(|| {
+    let mut map = HashMap::new();
+
+    map.insert(TypeId::of::<GlobalTransform>(), Transform::type_info());
+
+    map
+});
+
+// How deep nested structs are walked, deeper fields go without metadata
+const maxMetadataDepth: usize = 4;
+
+fn typeMetadata(typeInfo: &TypeInfo) -> FieldMetadata {
+    let mut metadata = FieldMetadata {
+        tooltip: typeInfo.docs().map(|docs| docs.trim().to_string()),
+        ..default()
+    };
+
+    if let TypeInfo::Enum(enumInfo) = typeInfo {
+        if !typeInfo.type_path().starts_with("core::option::Option<") {
+            metadata.variants = Some(enumInfo.variant_names().iter().map(|name| name.to_string()).collect());
+        }
+    }
+
+    return metadata;
+}
+
+fn attributeMetadata(attributes: &bevy::reflect::attributes::CustomAttributes, docs: Option<&str>) -> FieldMetadata {
+    let mut metadata = FieldMetadata {
+        tooltip: docs.map(|docs| docs.trim().to_string()),
+        readOnly: attributes.contains::<EditorReadOnly>(),
+        unit: attributes.get::<EditorUnit>().map(|unit| unit.0.to_string()),
+        ..default()
+    };
+
+    if let Some(range) = attributes.get::<EditorRange>() {
+        metadata.min = Some(range.min);
+        metadata.max = Some(range.max);
+        metadata.step = range.step;
+    } else if let Some(range) = attributes.get::<RangeInclusive<f32>>() {
+        metadata.min = Some(*range.start() as f64);
+        metadata.max = Some(*range.end() as f64);
+    } else if let Some(range) = attributes.get::<RangeInclusive<f64>>() {
+        metadata.min = Some(*range.start());
+        metadata.max = Some(*range.end());
+    }
+
+    return metadata;
+}
+
+fn collectFields(typeInfo: &TypeInfo, path: &str, depth: usize, out: &mut BTreeMap<String, FieldMetadata>) {
+    if depth >= maxMetadataDepth { return; }
+
+    let fields: Vec<(String, Option<&'static TypeInfo>, FieldMetadata)> = match typeInfo {
+        TypeInfo::Struct(structInfo) => structInfo.iter().map(|field: &NamedField| {
+            (field.name().to_string(), field.type_info(), attributeMetadata(field.custom_attributes(), field.docs()))
+        }).collect(),
+        TypeInfo::TupleStruct(tupleInfo) => tupleInfo.iter().map(|field: &UnnamedField| {
+            (field.index().to_string(), field.type_info(), attributeMetadata(field.custom_attributes(), field.docs()))
+        }).collect(),
+        // Paths into enums refer to fields of whichever variant is active, so the fields of all variants are collected
+        TypeInfo::Enum(enumInfo) => enumInfo.iter().flat_map(|variant| match variant {
+            VariantInfo::Struct(structVariant) => structVariant.iter().map(|field: &NamedField| {
+                (field.name().to_string(), field.type_info(), attributeMetadata(field.custom_attributes(), field.docs()))
+            }).collect::<Vec<_>>(),
+            VariantInfo::Tuple(tupleVariant) => tupleVariant.iter().map(|field: &UnnamedField| {
+                (field.index().to_string(), field.type_info(), attributeMetadata(field.custom_attributes(), field.docs()))
+            }).collect::<Vec<_>>(),
+            VariantInfo::Unit(_) => vec![],
+        }).collect(),
+        _ => return,
+    };
+
+    for (name, fieldType, attributes) in fields {
+        let fieldPath = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+
+        let mut metadata = fieldType.map(typeMetadata).unwrap_or_default();
+        // Type docs are too generic for a field (e.g. the docs of `f32`), field docs replace them
+        if fieldType.is_some_and(|info| matches!(info, TypeInfo::Opaque(_))) {
+            metadata.tooltip = None;
+        }
+        if let Some(builtin) = BuiltinMetadata.get(&(typeInfo.type_id(), name.as_str())) {
+            metadata.merge(builtin);
+        }
+        metadata.merge(&attributes);
+
+        if !metadata.isEmpty() {
+            out.insert(fieldPath.clone(), metadata);
+        }
+        if let Some(fieldType) = fieldType {
+            collectFields(fieldType, &fieldPath, depth + 1, out);
+        }
+    }
+}
+
+// Metadata for every field of a component (or any other reflected type) that has some, keyed by the reflection path `setProperty` takes
+pub fn typeFieldMetadata(typeInfo: &TypeInfo, registered: Option<&EditorComponentInfo>) -> BTreeMap<String, FieldMetadata> {
+    let mut out = BTreeMap::new();
+    let typeInfo = EditedAsTypes.get(&typeInfo.type_id()).copied().unwrap_or(typeInfo);
+
+    let mut own = typeMetadata(typeInfo);
+    if let Some(builtin) = BuiltinMetadata.get(&(typeInfo.type_id(), "")) {
+        own.merge(builtin);
+    }
+    if !own.isEmpty() {
+        out.insert(String::new(), own);
+    }
+
+    collectFields(typeInfo, "", 0, &mut out);
+
+    if let Some(registered) = registered {
+        for (path, metadata) in registered.fields.iter() {
+            out.entry(path.clone()).or_default().merge(metadata);
+        }
+    }
+
+    return out;
+}
@@ -0,0 +1,52 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde_json::Value;
+
+// "Box.001" and "Box" share the base name "Box"
+fn baseName(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((base, suffix)) if !base.is_empty() && suffix.len() == 3 && suffix.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+// The name itself if nothing uses it yet, otherwise the first free "name.001", "name.002" and so on
+pub fn uniqueName(taken: &HashSet<String>, name: &str) -> String {
+    if !taken.contains(name) {
+        return name.to_string();
+    }
+
+    let base = baseName(name);
+    (1..).map(|i| format!("{}.{:03}", base, i))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default()
+}
+
+pub fn takenNames(world: &World) -> HashSet<String> {
+    world.iter_entities()
+        .filter_map(|entity| entity.get::<Name>().map(|name| name.as_str().to_string()))
+        .collect()
+}
+
+// What new entities without a name are called, by what they are
+pub fn defaultName(entity: EntityRef) -> &'static str {
+    if entity.contains::<Camera3d>() { "Camera" }
+    else if entity.contains::<DirectionalLight>() { "Directional Light" }
+    else if entity.contains::<PointLight>() { "Point Light" }
+    else if entity.contains::<SpotLight>() { "Spot Light" }
+    else if entity.contains::<Mesh3d>() { "Mesh" }
+    else { "Empty" }
+}
+
+// The inspector shows names as plain strings
+pub fn nameInfo(name: &Name) -> Value {
+    Value::from(name.as_str())
+}
+
+// Returns the previous name, empty if the entity had none
+pub fn renameEntity(world: &mut World, entity: Entity, name: &str) -> Result<String, String> {
+    let mut entityMut = world.get_entity_mut(entity).map_err(|e| e.to_string())?;
+    let previous = entityMut.get::<Name>().map(|name| name.as_str().to_string()).unwrap_or_default();
+
+    entityMut.insert(Name::new(name.to_string()));
+    Ok(previous)
+}
@@ -0,0 +1,218 @@
+use bevy::{prelude::*, render::primitives::Aabb};
+use serde_derive::{Deserialize, Serialize};
+use transform_gizmo_bevy::GizmoResult;
+
+// What rotating and scaling the selection pivots around, like Blender's pivot point setting
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PivotMode {
+    BoundsCenter, // center of the box around all selected entities
+    #[default]
+    MedianPoint, // average of the selected entities' origins
+    IndividualOrigins, // every entity rotates and scales about its own origin
+    ActiveObject, // origin of the most recently selected entity
+    Cursor, // the 3D cursor
+}
+
+impl PivotMode {
+    // Order the pivot hotkey cycles through
+    pub const All: [PivotMode; 5] = [PivotMode::BoundsCenter, PivotMode::MedianPoint, PivotMode::IndividualOrigins, PivotMode::ActiveObject, PivotMode::Cursor];
+
+    pub fn next(self) -> Self {
+        let index = Self::All.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::All[(index + 1) % Self::All.len()]
+    }
+
+    // The gizmo pivots around the median or individual origins by itself, other modes are applied on top of it
+    pub fn isNative(self) -> bool {
+        matches!(self, PivotMode::MedianPoint | PivotMode::IndividualOrigins)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PivotSettings {
+    pub mode: PivotMode,
+}
+
+// World transforms of the selection from before the current gizmo drag, which non-native pivots are applied to
+#[derive(Resource, Default)]
+pub struct PivotDrag {
+    pub dragging: bool,
+    pub start: Vec<(Entity, Transform, Option<GlobalTransform>)>, // world transform and parent's global transform of every target
+    pub pivot: Option<Vec3>, // kept after the drag, so the history entry can use it
+    pub result: Option<GizmoResult>, // latest result of the drag, set once more after it ended
+}
+
+// A selected entity as the pivot sees it: its world transform and world-space bounding box, if it has one
+pub struct PivotTarget {
+    pub entity: Entity,
+    pub transform: Transform,
+    pub bounds: Option<(Vec3, Vec3)>,
+}
+
+// The eight corners of a bounding box, in world space
+pub fn aabbCorners(aabb: &Aabb, global: &GlobalTransform) -> [Vec3; 8] {
+    let center = Vec3::from(aabb.center);
+    let halfExtents = Vec3::from(aabb.half_extents);
+
+    std::array::from_fn(|corner| {
+        let sign = Vec3::new(
+            if corner & 1 == 0 { -1. } else { 1. },
+            if corner & 2 == 0 { -1. } else { 1. },
+            if corner & 4 == 0 { -1. } else { 1. },
+        );
+        global.transform_point(center + halfExtents * sign)
+    })
+}
+
+pub fn worldBounds(aabb: &Aabb, global: &GlobalTransform) -> (Vec3, Vec3) {
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for point in aabbCorners(aabb, global) {
+        min = min.min(point);
+        max = max.max(point);
+    }
+
+    return (min, max);
+}
+
+// The point the selection pivots around, None for individual origins
+pub fn pivotPoint(mode: PivotMode, targets: &[PivotTarget], active: Option<Entity>, cursor: Option<Vec3>) -> Option<Vec3> {
+    if targets.is_empty() { return None; }
+    let median = targets.iter().map(|target| target.transform.translation).sum::<Vec3>() / targets.len() as f32;
+
+    match mode {
+        PivotMode::BoundsCenter => {
+            let (min, max) = targets.iter()
+                .map(|target| target.bounds.unwrap_or((target.transform.translation, target.transform.translation)))
+                .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), (targetMin, targetMax)| (min.min(targetMin), max.max(targetMax)));
+            Some((min + max) / 2.)
+        },
+        PivotMode::MedianPoint => Some(median),
+        PivotMode::IndividualOrigins => None,
+        PivotMode::ActiveObject => Some(
+            targets.iter().find(|target| Some(target.entity) == active).map_or(median, |target| target.transform.translation)
+        ),
+        PivotMode::Cursor => Some(cursor.unwrap_or(median)),
+    }
+}
+
+// World transform of an entity after the gizmo rotated or scaled it by `result` around `pivot` (its own origin if None).
+// Rotations are in world space, the same way undoing and redoing a group transform applies them
+pub fn pivotTransform(start: Transform, result: &GizmoResult, pivot: Option<Vec3>) -> Transform {
+    let pivot = pivot.unwrap_or(start.translation);
+    let mut transform = start;
+
+    match *result {
+        GizmoResult::Translation { delta: _, total } => {
+            transform.translation += Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+        },
+        GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
+            let rotation = Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), total as f32);
+            transform.translation = pivot + rotation * (start.translation - pivot);
+            transform.rotation = rotation * start.rotation;
+        },
+        GizmoResult::Scale { total } => {
+            let scale = Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+            transform.translation = pivot + (start.translation - pivot) * scale;
+            transform.scale *= scale;
+        },
+        _ => {},
+    }
+
+    return transform;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn target(index: u32, translation: Vec3, bounds: Option<(Vec3, Vec3)>) -> PivotTarget {
+        PivotTarget { entity: Entity::from_raw(index), transform: Transform::from_translation(translation), bounds }
+    }
+
+    fn targets() -> Vec<PivotTarget> {
+        vec![
+            target(0, Vec3::new(0., 0., 0.), Some((Vec3::splat(-1.), Vec3::splat(1.)))),
+            target(1, Vec3::new(4., 0., 0.), Some((Vec3::new(3., -1., -1.), Vec3::new(9., 1., 1.)))),
+            target(2, Vec3::new(2., 3., 0.), None),
+        ]
+    }
+
+    fn rotationY(angle: f32) -> GizmoResult {
+        GizmoResult::Rotation { axis: [0., 1., 0.].into(), delta: angle as f64, total: angle as f64, is_view_axis: false }
+    }
+
+    #[test]
+    fn pivotPoints() {
+        let targets = targets();
+        let cursor = Vec3::new(5., 5., 5.);
+        let median = Vec3::new(2., 1., 0.);
+
+        assert_eq!(pivotPoint(PivotMode::MedianPoint, &targets, None, None), Some(median));
+        // Entities without bounds count as a point
+        assert_eq!(pivotPoint(PivotMode::BoundsCenter, &targets, None, None), Some(Vec3::new(4., 1., 0.)));
+        assert_eq!(pivotPoint(PivotMode::IndividualOrigins, &targets, None, Some(cursor)), None);
+        assert_eq!(pivotPoint(PivotMode::ActiveObject, &targets, Some(Entity::from_raw(1)), None), Some(Vec3::new(4., 0., 0.)));
+        assert_eq!(pivotPoint(PivotMode::Cursor, &targets, None, Some(cursor)), Some(cursor));
+    }
+
+    #[test]
+    fn pivotPointFallsBackToTheMedian() {
+        let targets = targets();
+        let median = Vec3::new(2., 1., 0.);
+
+        assert_eq!(pivotPoint(PivotMode::ActiveObject, &targets, None, None), Some(median));
+        assert_eq!(pivotPoint(PivotMode::ActiveObject, &targets, Some(Entity::from_raw(7)), None), Some(median));
+        assert_eq!(pivotPoint(PivotMode::Cursor, &targets, None, None), Some(median));
+        assert_eq!(pivotPoint(PivotMode::MedianPoint, &[], None, None), None);
+    }
+
+    #[test]
+    fn translationIgnoresThePivot() {
+        let start = Transform::from_xyz(1., 2., 3.);
+        let result = GizmoResult::Translation { delta: [1., 0., 0.].into(), total: [1., 0., -1.].into() };
+
+        assert_eq!(pivotTransform(start, &result, Some(Vec3::splat(10.))).translation, Vec3::new(2., 2., 2.));
+    }
+
+    #[test]
+    fn rotatesAroundThePivotInWorldSpace() {
+        let start = Transform::from_xyz(3., 0., 0.).with_rotation(Quat::from_rotation_x(FRAC_PI_2));
+        let rotated = pivotTransform(start, &rotationY(FRAC_PI_2), Some(Vec3::new(1., 0., 0.)));
+
+        assert!(rotated.translation.abs_diff_eq(Vec3::new(1., 0., -2.), 1e-5));
+        assert!(rotated.rotation.abs_diff_eq(Quat::from_rotation_y(FRAC_PI_2) * Quat::from_rotation_x(FRAC_PI_2), 1e-5));
+    }
+
+    #[test]
+    fn rotatesInPlaceWithoutAPivot() {
+        let start = Transform::from_xyz(3., 0., 0.);
+        let rotated = pivotTransform(start, &rotationY(FRAC_PI_2), None);
+
+        assert!(rotated.translation.abs_diff_eq(start.translation, 1e-5));
+        assert!(rotated.rotation.abs_diff_eq(Quat::from_rotation_y(FRAC_PI_2), 1e-5));
+    }
+
+    #[test]
+    fn scalesAwayFromThePivot() {
+        let start = Transform::from_xyz(3., 1., 0.);
+        let result = GizmoResult::Scale { total: [2., 2., 2.].into() };
+        let scaled = pivotTransform(start, &result, Some(Vec3::new(1., 1., 0.)));
+
+        assert_eq!(scaled.translation, Vec3::new(5., 1., 0.));
+        assert_eq!(scaled.scale, Vec3::splat(2.));
+    }
+
+    #[test]
+    fn worldBoundsFollowTheTransform() {
+        let aabb = Aabb::from_min_max(Vec3::new(-1., -2., -3.), Vec3::new(1., 2., 3.));
+        let global = GlobalTransform::from(Transform::from_xyz(10., 0., 0.).with_rotation(Quat::from_rotation_y(FRAC_PI_2)));
+        let (min, max) = worldBounds(&aabb, &global);
+
+        assert!(min.abs_diff_eq(Vec3::new(7., -2., -1.), 1e-5));
+        assert!(max.abs_diff_eq(Vec3::new(13., 2., 1.), 1e-5));
+    }
+}
@@ -0,0 +1,246 @@
+use std::any::TypeId;
+
+use bevy::{
+    ecs::reflect::{ReflectComponent, ReflectFromWorld, ReflectResource},
+    prelude::*,
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        PartialReflect,
+        ReflectFromReflect,
+        ReflectPath,
+        std_traits::ReflectDefault,
+        TypeInfo,
+        TypeRegistry,
+    },
+    utils::HashSet,
+};
+use bevy_mod_outline::{OutlineStencil, OutlineVolume};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeSeed;
+use transform_gizmo_bevy::GizmoTarget;
+
+use crate::{EditorCursor, EditorId, MoveArrows, RotateArrows, RotationCamera, ScaleArrows};
+
+use super::{editorvisibility::isEditorHiddenType, inspector::fromInspectorValue, marquee::MarqueeOverlay, naming::renameEntity};
+use serde_json::{json, Value};
+
+// Components the editor relies on, removing them from the inspector would break the entity or the editor itself
+static RequiredComponentTypes: Lazy<HashSet<TypeId>> = Lazy::new(|| {
+    let mut set = HashSet::new();
+
+    set.insert(TypeId::of::<Children>());
+    set.insert(TypeId::of::<EditorCursor>());
+    set.insert(TypeId::of::<EditorId>());
+    set.insert(TypeId::of::<GizmoTarget>());
+    set.insert(TypeId::of::<GlobalTransform>());
+    set.insert(TypeId::of::<MarqueeOverlay>());
+    set.insert(TypeId::of::<MoveArrows>());
+    set.insert(TypeId::of::<OutlineStencil>());
+    set.insert(TypeId::of::<OutlineVolume>());
+    set.insert(TypeId::of::<Parent>());
+    set.insert(TypeId::of::<RotateArrows>());
+    set.insert(TypeId::of::<RotationCamera>());
+    set.insert(TypeId::of::<ScaleArrows>());
+    set.insert(TypeId::of::<Transform>());
+
+    set
+});
+
+// Accepts both full type paths (as sent in the "properties" callback) and short ones, e.g. "Transform"
+pub fn componentTypeId(registry: &TypeRegistry, componentName: &str) -> Result<TypeId, String> {
+    registry.get_with_type_path(componentName)
+        .or_else(|| registry.get_with_short_type_path(componentName))
+        .filter(|registration| registration.data::<ReflectComponent>().is_some())
+        .map(|registration| registration.type_id())
+        .ok_or_else(|| format!("{} is not a registered component", componentName))
+}
+
+pub fn resourceTypeId(registry: &TypeRegistry, resourceName: &str) -> Result<TypeId, String> {
+    registry.get_with_type_path(resourceName)
+        .or_else(|| registry.get_with_short_type_path(resourceName))
+        .filter(|registration| registration.data::<ReflectResource>().is_some())
+        .map(|registration| registration.type_id())
+        .ok_or_else(|| format!("{} is not a registered resource", resourceName))
+}
+
+pub fn entityFromBits(bits: u64) -> Result<Entity, String> {
+    Entity::try_from_bits(bits).map_err(|_| format!("{} is not a valid entity id", bits))
+}
+
+pub fn serializeValue(value: &dyn PartialReflect, registry: &TypeRegistry) -> Result<Value, String> {
+    serde_json::to_value(TypedReflectSerializer::new(value, registry)).map_err(|e| e.to_string())
+}
+
+pub fn deserializeTyped(typeInfo: &TypeInfo, value: &Value, registry: &TypeRegistry) -> Result<Box<dyn PartialReflect>, String> {
+    let registration = registry.get(typeInfo.type_id()).ok_or_else(|| format!("{} is not registered", typeInfo.type_path()))?;
+
+    TypedReflectDeserializer::new(registration, registry)
+        .deserialize(value)
+        .map_err(|e| format!("Expected a value of type {}: {}", typeInfo.type_path(), e))
+}
+
+// Builds a value of the same type as `target` out of either its serde representation or the shape `inspectValue` gives it
+pub fn deserializeValue(target: &dyn PartialReflect, value: &Value, registry: &TypeRegistry) -> Result<Box<dyn PartialReflect>, String> {
+    let typeInfo = target.get_represented_type_info().ok_or("Property has no type information")?;
+
+    deserializeTyped(typeInfo, value, registry)
+        .or_else(|serdeError| fromInspectorValue(typeInfo, value, registry).map_err(|_| serdeError))
+}
+
+// An empty property path refers to the whole component
+pub fn readProperty(world: &World, entity: Entity, componentName: &str, property: &str) -> Result<Value, String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let typeId = componentTypeId(&registry, componentName)?;
+
+    if typeId == TypeId::of::<Name>() {
+        return world.get::<Name>(entity).map(|name| Value::from(name.as_str())).ok_or_else(|| "Entity has no name".to_string());
+    }
+    if typeId == TypeId::of::<GlobalTransform>() {
+        let global = world.get::<GlobalTransform>(entity).ok_or("Entity has no GlobalTransform")?;
+        let decomposed = global.compute_transform();
+        let field = property.reflect_element(decomposed.as_partial_reflect()).map_err(|e| format!("Invalid property {}: {}", property, e))?;
+        return serializeValue(field, &registry);
+    }
+
+    let component = world.get_reflect(entity, typeId).map_err(|e| e.to_string())?;
+    let field = property.reflect_element(component.as_partial_reflect()).map_err(|e| format!("Invalid property {}: {}", property, e))?;
+
+    serializeValue(field, &registry)
+}
+
+// Writes a JSON value into a component field through reflection, returning the value that was there before
+pub fn writeProperty(world: &mut World, entity: Entity, componentName: &str, property: &str, value: &Value) -> Result<Value, String> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let typeId = componentTypeId(&registry, componentName)?;
+
+    if typeId == TypeId::of::<GlobalTransform>() {
+        return writeGlobalTransform(world, entity, property, value, &registry);
+    }
+    // Names keep a hash of themselves, which reflection doesn't update
+    if typeId == TypeId::of::<Name>() {
+        if !property.is_empty() { return Err("Names can only be set as a whole".to_string()); }
+
+        let name = value.as_str().ok_or("A name has to be a string")?;
+        return renameEntity(world, entity, name).map(Value::from);
+    }
+
+    let mut component = world.get_reflect_mut(entity, typeId).map_err(|e| e.to_string())?;
+    writeReflectPath(component.as_partial_reflect_mut(), property, value, &registry)
+}
+
+// Global transforms are shown in the shape of a `Transform`, in world space
+pub fn globalTransformInfo(global: &GlobalTransform) -> Value {
+    let (scale, rotation, translation) = global.to_scale_rotation_translation();
+
+    json!({
+        "translation": { "x": translation.x, "y": translation.y, "z": translation.z },
+        "rotation": { "x": rotation.x, "y": rotation.y, "z": rotation.z, "w": rotation.w },
+        "scale": { "x": scale.x, "y": scale.y, "z": scale.z },
+    })
+}
+
+// Global transforms are computed from the hierarchy every frame, so an edit is turned into the local transform resulting in it
+fn writeGlobalTransform(world: &mut World, entity: Entity, property: &str, value: &Value, registry: &TypeRegistry) -> Result<Value, String> {
+    let global = *world.get::<GlobalTransform>(entity).ok_or("Entity has no GlobalTransform")?;
+    let mut decomposed = global.compute_transform();
+    let previous = writeReflectPath(decomposed.as_partial_reflect_mut(), property, value, registry)?;
+
+    let newGlobal = GlobalTransform::from(decomposed);
+    let local = match world.get::<Parent>(entity).and_then(|parent| world.get::<GlobalTransform>(parent.get())) {
+        Some(parentGlobal) => newGlobal.reparented_to(parentGlobal),
+        None => decomposed,
+    };
+
+    // The global transform is set as well, so reading it back before propagation runs gives the edited value
+    world.entity_mut(entity).insert((local, newGlobal));
+    Ok(previous)
+}
+
+pub fn writeResourceProperty(world: &mut World, resourceName: &str, property: &str, value: &Value) -> Result<Value, String> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let typeId = resourceTypeId(&registry, resourceName)?;
+
+    let reflectResource = registry.get_type_data::<ReflectResource>(typeId).ok_or_else(|| format!("{} is not a registered resource", resourceName))?;
+    let mut resource = reflectResource.reflect_mut(world).ok_or_else(|| format!("{} doesn't exist", resourceName))?;
+    writeReflectPath(resource.as_partial_reflect_mut(), property, value, &registry)
+}
+
+// Same as `writeProperty`, for any reflected value (e.g. assets)
+pub fn writeReflectPath(target: &mut dyn PartialReflect, property: &str, value: &Value, registry: &TypeRegistry) -> Result<Value, String> {
+    let field = property.reflect_element_mut(target).map_err(|e| format!("Invalid property {}: {}", property, e))?;
+
+    let newValue = deserializeValue(field, value, registry)?;
+    let previous = serializeValue(field, registry)?;
+    field.try_apply(&*newValue).map_err(|e| e.to_string())?;
+
+    Ok(previous)
+}
+
+// Default value of a component, for components that can be built without any input
+pub fn defaultComponent(world: &mut World, typeId: TypeId) -> Option<Box<dyn Reflect>> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let registration = registry.get(typeId)?;
+
+    if let Some(reflectDefault) = registration.data::<ReflectDefault>() {
+        return Some(reflectDefault.default());
+    }
+    registration.data::<ReflectFromWorld>().map(|fromWorld| fromWorld.from_world(world))
+}
+
+pub fn deserializeComponent(registry: &TypeRegistry, componentName: &str, value: &Value) -> Result<Box<dyn PartialReflect>, String> {
+    let registration = registry.get(componentTypeId(registry, componentName)?).ok_or_else(|| format!("{} is not registered", componentName))?;
+    let typeInfo = registration.type_info();
+
+    deserializeTyped(typeInfo, value, registry)
+        .or_else(|serdeError| fromInspectorValue(typeInfo, value, registry).map_err(|_| serdeError))
+}
+
+// Inserts a component, or its default value when none is given, returning a copy of what was inserted
+pub fn insertComponent(world: &mut World, entity: Entity, componentName: &str, component: Option<&dyn PartialReflect>) -> Result<Box<dyn PartialReflect>, String> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let typeId = componentTypeId(&registry, componentName)?;
+
+    if world.get_entity(entity).map_err(|e| e.to_string())?.contains_type_id(typeId) {
+        return Err(format!("Entity already has {}", componentName));
+    }
+
+    let component = match component {
+        Some(component) => component.clone_value(),
+        None => defaultComponent(world, typeId)
+            .ok_or_else(|| format!("{} has no default value, so a value has to be given", componentName))?
+            .into_partial_reflect(),
+    };
+
+    // Inserting a value that doesn't fit the type would panic inside of bevy, so it is turned into the concrete type first
+    let reflectFromReflect = registry.get_type_data::<ReflectFromReflect>(typeId).ok_or_else(|| format!("{} can't be built from a reflected value", componentName))?;
+    let component = reflectFromReflect.from_reflect(&*component).ok_or_else(|| format!("The value doesn't fit {}", componentName))?;
+
+    let reflectComponent = registry.get_type_data::<ReflectComponent>(typeId).ok_or_else(|| format!("{} is not a registered component", componentName))?;
+    let mut entityMut = world.get_entity_mut(entity).map_err(|e| e.to_string())?;
+    reflectComponent.insert(&mut entityMut, component.as_partial_reflect(), &registry);
+
+    Ok(component.into_partial_reflect())
+}
+
+// Removes a component, returning its value so the removal can be undone
+pub fn removeComponent(world: &mut World, entity: Entity, componentName: &str) -> Result<Box<dyn PartialReflect>, String> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let typeId = componentTypeId(&registry, componentName)?;
+
+    if RequiredComponentTypes.contains(&typeId) || isEditorHiddenType(typeId) {
+        return Err(format!("{} is managed by the editor and can't be removed", componentName));
+    }
+
+    let removed = world.get_reflect(entity, typeId).map_err(|e| e.to_string())?.clone_value();
+
+    let reflectComponent = registry.get_type_data::<ReflectComponent>(typeId).ok_or_else(|| format!("{} is not a registered component", componentName))?;
+    let mut entityMut = world.get_entity_mut(entity).map_err(|e| e.to_string())?;
+    reflectComponent.remove(&mut entityMut);
+
+    Ok(removed)
+}
@@ -0,0 +1,716 @@
+use std::{any::TypeId, collections::BTreeMap};
+
+use bevy::{
+    asset::ReflectHandle,
+    ecs::reflect::ReflectComponent,
+    reflect::{TypeInfo, TypeRegistry, VariantInfo},
+};
+use serde_json::{json, Map, Value};
+
+use super::editorvisibility::isEditorHiddenType;
+
+// Describes everything exchanged with the frontend as JSON Schema: the commands `Runner::sendEvent` takes, the callbacks
+// triggered through `triggerInterfaceCallbacks` and the inspector shape of every registered component. The protocol part is
+// written by hand, the tests check its command and callback names against the code sending and reading messages. TypeScript definitions are generated from it
+
+// How deep nested component fields are described, deeper ones are left as `unknown`
+const maxSchemaDepth: usize = 6;
+
+fn object(properties: Vec<(&str, Value)>, optional: &[&str]) -> Value {
+    let required = properties.iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !optional.contains(name))
+        .collect::<Vec<&str>>();
+
+    json!({
+        "type": "object",
+        "properties": properties.into_iter().map(|(name, schema)| (name.to_string(), schema)).collect::<Map<String, Value>>(),
+        "required": required,
+    })
+}
+
+fn arrayOf(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn nullable(inner: Value) -> Value {
+    json!({ "anyOf": [inner, { "type": "null" }] })
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", name) })
+}
+
+fn described(mut schema: Value, description: &str) -> Value {
+    schema["description"] = description.into();
+    schema
+}
+
+fn number() -> Value { json!({ "type": "number" }) }
+fn string() -> Value { json!({ "type": "string" }) }
+fn boolean() -> Value { json!({ "type": "boolean" }) }
+fn anything() -> Value { json!({}) }
+
+fn protocolDefinitions() -> Vec<(&'static str, Value)> {
+    let resultOf = |callback: &str| described(reference("RequestResult"), &format!("Answer to the command, see \"{}\"", callback));
+
+    vec![
+        ("EntityBits", described(number(), "An entity, as its bits")),
+        ("ValuePath", described(arrayOf(json!({ "anyOf": [string(), number()] })), "Object keys and array indices leading to a value inside an inspector value")),
+        ("RequestResult", object(vec![
+            ("requestId", nullable(number())),
+            ("success", boolean()),
+            ("error", nullable(string())),
+        ], &[])),
+        ("FieldMetadata", object(vec![
+            ("min", number()),
+            ("max", number()),
+            ("step", number()),
+            ("unit", string()),
+            ("tooltip", string()),
+            ("readOnly", boolean()),
+            ("variants", arrayOf(string())),
+        ], &["min", "max", "step", "unit", "tooltip", "readOnly", "variants"])),
+        ("MetadataMap", described(json!({ "type": "object", "additionalProperties": reference("FieldMetadata") }), "Field metadata keyed by reflection path, an empty path refers to the whole value")),
+        ("MaterialInfo", object(vec![
+            ("id", string()),
+            ("users", described(number(), "Number of entities sharing the material")),
+            ("properties", reference("StandardMaterial")),
+            ("metadata", reference("MetadataMap")),
+        ], &[])),
+        ("ComponentProperties", object(vec![
+            ("entity", reference("EntityBits")),
+            ("name", string()),
+            ("info", anything()),
+            ("metadata", reference("MetadataMap")),
+            ("displayName", string()),
+            ("category", string()),
+            ("icon", string()),
+            ("material", reference("MaterialInfo")),
+            ("entities", described(arrayOf(reference("EntityBits")), "Set when several entities are inspected at once")),
+            ("mixed", described(arrayOf(reference("ValuePath")), "Fields that differ between the inspected entities")),
+        ], &["metadata", "displayName", "category", "icon", "material", "entities", "mixed"])),
+        ("PropertyChanges", json!({ "anyOf": [
+            object(vec![
+                ("entity", reference("EntityBits")),
+                ("name", string()),
+                ("changes", arrayOf(object(vec![("path", reference("ValuePath")), ("value", anything())], &[]))),
+            ], &[]),
+            object(vec![
+                ("entity", reference("EntityBits")),
+                ("despawned", json!({ "const": true })),
+            ], &[]),
+        ] })),
+        ("ResourceProperties", object(vec![
+            ("name", string()),
+            ("info", anything()),
+            ("metadata", reference("MetadataMap")),
+        ], &[])),
+        ("InsertableComponent", object(vec![
+            ("name", string()),
+            ("shortName", string()),
+            ("default", anything()),
+            ("displayName", string()),
+            ("category", string()),
+            ("icon", string()),
+        ], &["displayName", "category", "icon"])),
+        ("ImageLoaded", object(vec![("id", string())], &[])),
+        ("HierarchyNode", object(vec![
+            ("entity", reference("EntityBits")),
+            ("name", nullable(string())),
+            ("parent", nullable(reference("EntityBits"))),
+            ("children", arrayOf(reference("EntityBits"))),
+            ("icon", json!({ "enum": ["camera", "directionalLight", "pointLight", "spotLight", "mesh", "empty"] })),
+            ("hidden", boolean()),
+            ("locked", boolean()),
+            ("layer", nullable(string())),
+        ], &[])),
+        ("HierarchyUpdate", json!({ "anyOf": [
+            object(vec![("type", json!({ "const": "snapshot" })), ("entities", arrayOf(reference("HierarchyNode")))], &[]),
+            object(vec![("type", json!({ "const": "added" })), ("node", reference("HierarchyNode"))], &[]),
+            object(vec![("type", json!({ "const": "removed" })), ("entity", reference("EntityBits"))], &[]),
+            object(vec![("type", json!({ "const": "renamed" })), ("entity", reference("EntityBits")), ("name", string())], &[]),
+            object(vec![("type", json!({ "const": "reparented" })), ("entity", reference("EntityBits")), ("parent", nullable(reference("EntityBits")))], &[]),
+            object(vec![("type", json!({ "const": "flags" })), ("entity", reference("EntityBits")), ("hidden", boolean()), ("locked", boolean())], &[]),
+            object(vec![("type", json!({ "const": "layer" })), ("entity", reference("EntityBits")), ("layer", nullable(string()))], &[]),
+        ] })),
+        ("AutosaveInfo", object(vec![
+            ("slot", number()),
+            ("timestamp", number()),
+            ("reason", json!({ "enum": ["timer", "history"] })),
+            ("snapshot", described(string(), "A `RecoverySnapshot` as JSON, to be handed back through `addRecoverySnapshot`")),
+        ], &[])),
+        ("RecoveryInfo", object(vec![
+            ("slot", number()),
+            ("timestamp", number()),
+            ("reason", string()),
+            ("entityCount", number()),
+        ], &[])),
+
+        ("SavedMesh", object(vec![
+            ("positions", arrayOf(arrayOf(number()))),
+            ("normals", nullable(arrayOf(arrayOf(number())))),
+            ("uvs", nullable(arrayOf(arrayOf(number())))),
+            ("indices", nullable(arrayOf(number()))),
+        ], &[])),
+        ("SavedMaterial", object(vec![
+            ("baseColour", arrayOf(number())),
+            ("emissive", arrayOf(number())),
+            ("metallic", number()),
+            ("roughness", number()),
+            ("reflectance", number()),
+            ("unlit", boolean()),
+            ("doubleSided", boolean()),
+        ], &[])),
+        ("SavedEntity", object(vec![
+            ("id", number()),
+            ("parent", nullable(number())),
+            ("mesh", nullable(string())),
+            ("material", nullable(string())),
+            ("components", json!({ "type": "object", "additionalProperties": anything() })),
+        ], &[])),
+        ("SavedScene", object(vec![
+            ("entities", arrayOf(reference("SavedEntity"))),
+            ("meshes", json!({ "type": "object", "additionalProperties": reference("SavedMesh") })),
+            ("materials", json!({ "type": "object", "additionalProperties": reference("SavedMaterial") })),
+            ("layers", arrayOf(reference("EditorLayer"))),
+        ], &["layers"])),
+        ("EditorLayer", object(vec![
+            ("name", string()),
+            ("visible", boolean()),
+            ("selectable", boolean()),
+            ("colour", described(arrayOf(number()), "Linear RGBA")),
+        ], &[])),
+        ("RecoverySnapshot", object(vec![
+            ("slot", number()),
+            ("timestamp", number()),
+            ("reason", string()),
+            ("scene", reference("SavedScene")),
+        ], &[])),
+        ("PropertyChange", object(vec![
+            ("path", string()),
+            ("before", nullable(anything())),
+            ("after", nullable(anything())),
+        ], &[])),
+        ("EntityDiff", object(vec![
+            ("id", number()),
+            ("kind", json!({ "enum": ["Added", "Removed", "Modified"] })),
+            ("properties", arrayOf(reference("PropertyChange"))),
+        ], &[])),
+        ("SceneDiff", object(vec![("entities", arrayOf(reference("EntityDiff")))], &[])),
+        ("PropertyConflict", object(vec![
+            ("id", number()),
+            ("kind", json!({ "enum": ["Property", "ModifiedRemoved", "RemovedModified", "MissingParent"] })),
+            ("path", described(string(), "Empty when a whole entity was modified on one side and removed on the other")),
+            ("base", nullable(anything())),
+            ("ours", nullable(anything())),
+            ("theirs", nullable(anything())),
+        ], &[])),
+        ("SceneMerge", object(vec![
+            ("scene", reference("SavedScene")),
+            ("conflicts", arrayOf(reference("PropertyConflict"))),
+        ], &[])),
+
+        ("SceneDiffInfo", object(vec![("requestId", number()), ("before", reference("SavedScene")), ("after", reference("SavedScene"))], &["requestId"])),
+        ("SceneMergeInfo", object(vec![("requestId", number()), ("base", reference("SavedScene")), ("ours", reference("SavedScene")), ("theirs", reference("SavedScene"))], &["requestId"])),
+        ("PropertyUpdateInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("entities", arrayOf(reference("EntityBits"))),
+            ("componentName", string()),
+            ("property", string()),
+            ("value", described(anything(), "In the shape the inspector shows it or its serde representation")),
+        ], &["requestId", "entity", "entities"])),
+        ("MaterialUpdateInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("property", string()),
+            ("value", described(anything(), "In the shape the inspector shows it or its serde representation")),
+            ("fork", boolean()),
+        ], &["requestId", "fork"])),
+        ("ComponentUpdateInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("componentName", string()),
+            ("value", described(anything(), "In the shape the inspector shows it or its serde representation")),
+        ], &["requestId", "value"])),
+        ("ResourceUpdateInfo", object(vec![
+            ("requestId", number()),
+            ("resourceName", string()),
+            ("property", string()),
+            ("value", described(anything(), "In the shape the inspector shows it or its serde representation")),
+        ], &["requestId"])),
+        ("RenameInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("name", string()),
+        ], &["requestId"])),
+        ("ReparentInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("parent", nullable(reference("EntityBits"))),
+            ("keepWorldTransform", described(boolean(), "Defaults to true")),
+        ], &["requestId", "keepWorldTransform"])),
+        ("SelectionInfo", object(vec![
+            ("requestId", number()),
+            ("entities", described(arrayOf(reference("EntityBits")), "The last one becomes the active entity, deselecting without a payload clears the selection")),
+        ], &["requestId", "entities"])),
+        ("Selection", object(vec![
+            ("entities", described(arrayOf(reference("EntityBits")), "In the order they were selected")),
+            ("active", nullable(reference("EntityBits"))),
+        ], &[])),
+        ("EditorFlagInfo", object(vec![
+            ("requestId", number()),
+            ("entities", arrayOf(reference("EntityBits"))),
+            ("value", boolean()),
+        ], &["requestId"])),
+        ("LayerUpdateInfo", object(vec![
+            ("requestId", number()),
+            ("name", string()),
+            ("visible", boolean()),
+            ("selectable", boolean()),
+            ("colour", described(arrayOf(number()), "Linear RGBA")),
+        ], &["requestId", "visible", "selectable", "colour"])),
+        ("LayerAssignmentInfo", object(vec![
+            ("requestId", number()),
+            ("entities", arrayOf(reference("EntityBits"))),
+            ("layer", nullable(string())),
+        ], &["requestId"])),
+        ("SearchInfo", object(vec![
+            ("requestId", number()),
+            ("query", described(string(), "Whitespace separated terms that all have to match: name substrings, re:<regex>, has:<component>, tag:<tag>, layer:<layer>, each negatable with a leading !. Terms with spaces are double quoted, e.g. layer:\"Set dressing\"")),
+            ("select", described(boolean(), "Replaces the selection with the results that can be selected")),
+        ], &["requestId", "select"])),
+        ("SearchResult", object(vec![
+            ("requestId", nullable(number())),
+            ("success", boolean()),
+            ("error", nullable(string())),
+            ("entities", arrayOf(reference("EntityBits"))),
+        ], &[])),
+        ("HoveredEntity", object(vec![
+            ("entity", nullable(reference("EntityBits"))),
+            ("name", nullable(string())),
+        ], &[])),
+        ("PivotMode", json!({ "enum": ["boundsCenter", "medianPoint", "individualOrigins", "activeObject", "cursor"] })),
+        ("PivotModeInfo", object(vec![("requestId", number()), ("mode", reference("PivotMode"))], &["requestId"])),
+        ("CursorInfo", object(vec![("requestId", number()), ("position", described(arrayOf(number()), "World space x, y and z"))], &["requestId"])),
+        ("Pivot", object(vec![
+            ("mode", reference("PivotMode")),
+            ("cursor", nullable(arrayOf(number()))),
+        ], &[])),
+        ("WatchInfo", object(vec![
+            ("requestId", number()),
+            ("entity", reference("EntityBits")),
+            ("components", arrayOf(string())),
+        ], &["requestId", "entity", "components"])),
+
+        ("SetPropertyResult", resultOf("setProperty")),
+        ("SetMaterialPropertyResult", resultOf("setMaterialProperty")),
+        ("SetResourcePropertyResult", resultOf("setResourceProperty")),
+        ("InsertComponentResult", resultOf("insertComponent")),
+        ("RemoveComponentResult", resultOf("removeComponent")),
+        ("RenameResult", resultOf("rename")),
+        ("ReparentResult", resultOf("reparent")),
+        ("SetHiddenResult", resultOf("setHidden")),
+        ("SetLockedResult", resultOf("setLocked")),
+        ("LayerResult", resultOf("updateLayer, removeLayer or assignLayer")),
+        ("LoadSceneResult", described(reference("RequestResult"), "Answer to \"loadScene\", whose request id is read from a \"requestId\" field next to the scene's own. The error lists whatever couldn't be restored")),
+        ("DiffScenesResult", resultOf("diffScenes")),
+        ("MergeScenesResult", resultOf("mergeScenes")),
+        ("WatchResult", resultOf("watch or unwatch")),
+        ("SelectionResult", resultOf("select, addToSelection or deselect")),
+        ("SetPivotModeResult", resultOf("setPivotMode")),
+        ("PlaceCursorResult", resultOf("placeCursor")),
+    ]
+}
+
+// Commands by the event type given to `sendEvent`, with the definition of the JSON `info` string, or None when it's ignored
+fn commands() -> Vec<(&'static str, Option<&'static str>)> {
+    vec![
+        ("undo", None),
+        ("redo", None),
+        ("restoreSnapshot", None),
+        ("discardSnapshots", None),
+        ("saveScene", None),
+        ("loadScene", Some("SavedScene")),
+        ("diffScenes", Some("SceneDiffInfo")),
+        ("mergeScenes", Some("SceneMergeInfo")),
+        ("setProperty", Some("PropertyUpdateInfo")),
+        ("setMaterialProperty", Some("MaterialUpdateInfo")),
+        ("listComponents", None),
+        ("insertComponent", Some("ComponentUpdateInfo")),
+        ("removeComponent", Some("ComponentUpdateInfo")),
+        ("inspectResources", None),
+        ("setResourceProperty", Some("ResourceUpdateInfo")),
+        ("watch", Some("WatchInfo")),
+        ("unwatch", Some("WatchInfo")),
+        ("rename", Some("RenameInfo")),
+        ("reparent", Some("ReparentInfo")),
+        ("select", Some("SelectionInfo")),
+        ("addToSelection", Some("SelectionInfo")),
+        ("deselect", Some("SelectionInfo")),
+        ("setHidden", Some("EditorFlagInfo")),
+        ("setLocked", Some("EditorFlagInfo")),
+        ("listLayers", None),
+        ("updateLayer", Some("LayerUpdateInfo")),
+        ("removeLayer", Some("LayerUpdateInfo")),
+        ("assignLayer", Some("LayerAssignmentInfo")),
+        ("search", Some("SearchInfo")),
+        ("setPivotMode", Some("PivotModeInfo")),
+        ("placeCursor", Some("CursorInfo")),
+        ("hierarchySnapshot", None),
+        ("describeProtocol", None),
+    ]
+}
+
+// Callbacks by type, with the definition of each item in the array they're called with
+fn callbacks() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("properties", "ComponentProperties"),
+        ("hierarchy", "HierarchyUpdate"),
+        ("selection", "Selection"),
+        ("hover", "HoveredEntity"),
+        ("pivot", "Pivot"),
+        ("propertyChanges", "PropertyChanges"),
+        ("resources", "ResourceProperties"),
+        ("insertableComponents", "InsertableComponent"),
+        ("setPropertyResult", "SetPropertyResult"),
+        ("setMaterialPropertyResult", "SetMaterialPropertyResult"),
+        ("setResourcePropertyResult", "SetResourcePropertyResult"),
+        ("insertComponentResult", "InsertComponentResult"),
+        ("removeComponentResult", "RemoveComponentResult"),
+        ("renameResult", "RenameResult"),
+        ("reparentResult", "ReparentResult"),
+        ("setHiddenResult", "SetHiddenResult"),
+        ("setLockedResult", "SetLockedResult"),
+        ("layers", "EditorLayer"),
+        ("layerResult", "LayerResult"),
+        ("searchResult", "SearchResult"),
+        ("loadSceneResult", "LoadSceneResult"),
+        ("diffScenesResult", "DiffScenesResult"),
+        ("mergeScenesResult", "MergeScenesResult"),
+        ("watchResult", "WatchResult"),
+        ("selectionResult", "SelectionResult"),
+        ("setPivotModeResult", "SetPivotModeResult"),
+        ("placeCursorResult", "PlaceCursorResult"),
+        ("imageLoaded", "ImageLoaded"),
+        ("scene", "SavedScene"),
+        ("sceneDiff", "SceneDiff"),
+        ("sceneMerge", "SceneMerge"),
+        ("autosave", "AutosaveInfo"),
+        ("recovery", "RecoveryInfo"),
+        ("protocol", "ProtocolDescription"),
+    ]
+}
+
+fn opaqueSchema(typeId: TypeId) -> Value {
+    let numbers = [
+        TypeId::of::<f32>(), TypeId::of::<f64>(),
+        TypeId::of::<i8>(), TypeId::of::<i16>(), TypeId::of::<i32>(), TypeId::of::<i64>(), TypeId::of::<isize>(),
+        TypeId::of::<u8>(), TypeId::of::<u16>(), TypeId::of::<u32>(), TypeId::of::<u64>(), TypeId::of::<usize>(),
+    ];
+
+    if numbers.contains(&typeId) {
+        number()
+    } else if typeId == TypeId::of::<bool>() {
+        boolean()
+    } else if typeId == TypeId::of::<String>() || typeId == TypeId::of::<&'static str>() || typeId == TypeId::of::<std::borrow::Cow<'static, str>>() {
+        string()
+    } else {
+        // Whatever serde makes of it, or its debug output, see `inspectValue`
+        anything()
+    }
+}
+
+fn fieldSchema(typeInfo: Option<&'static TypeInfo>, registry: &TypeRegistry, depth: usize) -> Value {
+    typeInfo.map_or_else(anything, |typeInfo| typeSchema(typeInfo, registry, depth + 1))
+}
+
+// The schema of what `inspectValue` makes of a value of this type
+pub fn typeSchema(typeInfo: &TypeInfo, registry: &TypeRegistry, depth: usize) -> Value {
+    if depth >= maxSchemaDepth { return anything(); }
+    if registry.get_type_data::<ReflectHandle>(typeInfo.type_id()).is_some() {
+        return object(vec![("id", string())], &[]);
+    }
+
+    match typeInfo {
+        TypeInfo::Struct(structInfo) => object(
+            structInfo.iter().map(|field| (field.name(), fieldSchema(field.type_info(), registry, depth))).collect(),
+            &[],
+        ),
+        TypeInfo::TupleStruct(tupleInfo) => json!({
+            "type": "array",
+            "prefixItems": tupleInfo.iter().map(|field| fieldSchema(field.type_info(), registry, depth)).collect::<Vec<Value>>(),
+            "minItems": tupleInfo.field_len(),
+            "maxItems": tupleInfo.field_len(),
+        }),
+        TypeInfo::Tuple(tupleInfo) => json!({
+            "type": "array",
+            "prefixItems": tupleInfo.iter().map(|field| fieldSchema(field.type_info(), registry, depth)).collect::<Vec<Value>>(),
+            "minItems": tupleInfo.field_len(),
+            "maxItems": tupleInfo.field_len(),
+        }),
+        TypeInfo::List(listInfo) => arrayOf(fieldSchema(listInfo.item_info(), registry, depth)),
+        TypeInfo::Array(arrayInfo) => json!({
+            "type": "array",
+            "items": fieldSchema(arrayInfo.item_info(), registry, depth),
+            "minItems": arrayInfo.capacity(),
+            "maxItems": arrayInfo.capacity(),
+        }),
+        TypeInfo::Set(setInfo) => arrayOf(fieldSchema(registry.get_type_info(setInfo.value_ty().id()), registry, depth)),
+        TypeInfo::Map(mapInfo) => arrayOf(json!({
+            "type": "array",
+            "prefixItems": [fieldSchema(mapInfo.key_info(), registry, depth), fieldSchema(mapInfo.value_info(), registry, depth)],
+            "minItems": 2,
+            "maxItems": 2,
+        })),
+        TypeInfo::Enum(enumInfo) => {
+            if typeInfo.type_path().starts_with("core::option::Option<") {
+                let inner = match enumInfo.variant("Some") {
+                    Some(VariantInfo::Tuple(some)) => some.field_at(0).map_or_else(anything, |field| fieldSchema(field.type_info(), registry, depth)),
+                    _ => anything(),
+                };
+                return nullable(inner);
+            }
+
+            json!({ "anyOf": enumInfo.iter().map(|variant| match variant {
+                VariantInfo::Struct(structVariant) => object(vec![
+                    ("variant", json!({ "const": variant.name() })),
+                    ("fields", object(structVariant.iter().map(|field| (field.name(), fieldSchema(field.type_info(), registry, depth))).collect(), &[])),
+                ], &[]),
+                VariantInfo::Tuple(tupleVariant) => object(vec![
+                    ("variant", json!({ "const": variant.name() })),
+                    ("fields", json!({
+                        "type": "array",
+                        "prefixItems": tupleVariant.iter().map(|field| fieldSchema(field.type_info(), registry, depth)).collect::<Vec<Value>>(),
+                    })),
+                ], &[]),
+                VariantInfo::Unit(_) => object(vec![("variant", json!({ "const": variant.name() }))], &[]),
+            }).collect::<Vec<Value>>() })
+        },
+        TypeInfo::Opaque(_) => opaqueSchema(typeInfo.type_id()),
+        #[allow(unreachable_patterns)]
+        _ => anything(),
+    }
+}
+
+fn identifier(name: &str) -> String {
+    let sanitized = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect::<String>();
+    sanitized.trim_matches('_').to_string()
+}
+
+// The full description, as a JSON Schema document whose `$defs` hold every message and component
+pub fn protocolSchema(registry: &TypeRegistry) -> Value {
+    let mut defs = protocolDefinitions().into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect::<BTreeMap<String, Value>>();
+
+    let mut components = Map::new();
+    let mut registrations = registry.iter()
+        .filter(|registration| registration.data::<ReflectComponent>().is_some() && !isEditorHiddenType(registration.type_id()))
+        .collect::<Vec<_>>();
+    registrations.sort_by_key(|registration| registration.type_info().type_path());
+
+    for registration in registrations {
+        let typeInfo = registration.type_info();
+
+        // Short names read better, the full path is only used when two components share one
+        let mut name = identifier(typeInfo.type_path_table().short_path());
+        if defs.contains_key(&name) {
+            name = identifier(typeInfo.type_path());
+        }
+
+        defs.insert(name.clone(), described(typeSchema(typeInfo, registry, 0), typeInfo.type_path()));
+        components.insert(typeInfo.type_path().to_string(), reference(&name));
+    }
+
+    // Material properties are sent along with components, so the material gets a definition as well
+    if let Some(material) = registry.get_with_short_type_path("StandardMaterial") {
+        defs.insert("StandardMaterial".to_string(), typeSchema(material.type_info(), registry, 0));
+    } else {
+        defs.insert("StandardMaterial".to_string(), anything());
+    }
+
+    defs.insert("ProtocolDescription".to_string(), object(vec![
+        ("typescript", string()),
+        ("schema", json!({ "type": "object" })),
+    ], &[]));
+
+    let commands = commands().into_iter()
+        .map(|(name, payload)| (name.to_string(), payload.map_or_else(|| json!({ "type": "null" }), reference)))
+        .collect::<Map<String, Value>>();
+    let callbacks = callbacks().into_iter()
+        .map(|(name, item)| (name.to_string(), arrayOf(reference(item))))
+        .collect::<Map<String, Value>>();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "BevyEditorProtocol",
+        "type": "object",
+        "properties": {
+            "commands": described(json!({ "type": "object", "properties": commands }), "The `info` of every `sendEvent` type, as JSON"),
+            "callbacks": described(json!({ "type": "object", "properties": callbacks }), "What every callback type is called with"),
+            "components": described(json!({ "type": "object", "properties": components }), "Inspector info of every component, by component name"),
+        },
+        "$defs": defs,
+    })
+}
+
+fn propertyKey(name: &str) -> String {
+    let isIdentifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if isIdentifier { name.to_string() } else { Value::from(name).to_string() }
+}
+
+// TypeScript for the subset of JSON Schema `protocolSchema` uses
+fn typescriptOf(schema: &Value, indent: usize) -> String {
+    if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+        return target.trim_start_matches("#/$defs/").to_string();
+    }
+    if let Some(constant) = schema.get("const") {
+        return constant.to_string();
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values.iter().map(Value::to_string).collect::<Vec<String>>().join(" | ");
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        return options.iter().map(|option| typescriptOf(option, indent)).collect::<Vec<String>>().join(" | ");
+    }
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("null") => "null".to_string(),
+        Some("string") => "string".to_string(),
+        Some("number") | Some("integer") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("array") => {
+            if let Some(items) = schema.get("prefixItems").and_then(Value::as_array) {
+                return format!("[{}]", items.iter().map(|item| typescriptOf(item, indent)).collect::<Vec<String>>().join(", "));
+            }
+
+            let item = schema.get("items").map_or_else(|| "unknown".to_string(), |items| typescriptOf(items, indent));
+            if item.contains(' ') { format!("({})[]", item) } else { format!("{}[]", item) }
+        },
+        Some("object") => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                let value = schema.get("additionalProperties").map_or_else(|| "unknown".to_string(), |value| typescriptOf(value, indent));
+                return format!("Record<string, {}>", value);
+            };
+
+            let required = schema.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+            let padding = "    ".repeat(indent + 1);
+
+            let mut out = "{\n".to_string();
+            for (name, property) in properties {
+                if let Some(description) = property.get("description").and_then(Value::as_str) {
+                    out += &format!("{}/** {} */\n", padding, description);
+                }
+
+                let optional = if required.contains(&Value::from(name.as_str())) { "" } else { "?" };
+                out += &format!("{}{}{}: {};\n", padding, propertyKey(name), optional, typescriptOf(property, indent + 1));
+            }
+            out += &format!("{}}}", "    ".repeat(indent));
+
+            out
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+pub fn protocolTypescript(schema: &Value) -> String {
+    let mut out = "// Generated by the game from its protocol description, don't edit by hand\n\n".to_string();
+
+    if let Some(defs) = schema.get("$defs").and_then(Value::as_object) {
+        for (name, def) in defs {
+            if let Some(description) = def.get("description").and_then(Value::as_str) {
+                out += &format!("/** {} */\n", description);
+            }
+            out += &format!("export type {} = {};\n\n", name, typescriptOf(def, 0));
+        }
+    }
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, group) in properties {
+            let mut typeName = name.clone();
+            typeName[..1].make_ascii_uppercase();
+
+            if let Some(description) = group.get("description").and_then(Value::as_str) {
+                out += &format!("/** {} */\n", description);
+            }
+            out += &format!("export interface {} {}\n\n", typeName, typescriptOf(group, 0));
+        }
+    }
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    // Everything that sends callbacks or reads commands, `data.rs` and `bridge.rs` are wasm only, so they are read as text
+    static sources: [&str; 4] = [
+        include_str!("../wasm/data.rs"),
+        include_str!("../systems/bridge.rs"),
+        include_str!("../systems/startup.rs"),
+        include_str!("../systems/update.rs"),
+    ];
+
+    // The string literal right after each occurrence of `prefix`
+    fn literalsAfter<'a>(source: &'a str, prefix: &str) -> Vec<&'a str> {
+        source.match_indices(prefix)
+            .filter_map(|(start, _)| source[start + prefix.len()..].split('"').next())
+            .collect()
+    }
+
+    // The event types matched in `Runner::sendEvent`, e.g. `"select" | "addToSelection" =>`
+    fn sentCommands() -> BTreeSet<String> {
+        let body = sources[0].split("pub fn sendEvent").nth(1).expect("sendEvent is gone");
+        body.lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with('"') && line.contains("=>"))
+            .flat_map(|line| line.split("=>").next().unwrap().split(" if ").next().unwrap().split('|').map(|name| name.trim().trim_matches('"').to_string()).collect::<Vec<String>>())
+            .collect()
+    }
+
+    #[test]
+    fn everyCommandIsDescribed() {
+        let described = commands().into_iter().map(|(name, _)| name.to_string()).collect::<BTreeSet<String>>();
+        assert_eq!(sentCommands(), described);
+    }
+
+    #[test]
+    fn everyCallbackIsDescribed() {
+        let described = callbacks().into_iter().map(|(name, _)| name).collect::<BTreeSet<&str>>();
+
+        let triggered = sources.iter()
+            .flat_map(|source| ["triggerInterfaceCallbacks(\"", "requestResult(\"", "requestResultWith(\"", "componentResult(\""].map(|prefix| literalsAfter(source, prefix)))
+            .flatten()
+            .collect::<BTreeSet<&str>>();
+        for callback in triggered.iter() {
+            assert!(described.contains(callback), "\"{}\" is triggered but not described", callback);
+        }
+
+        // Some callbacks are picked at runtime, so only check that the name shows up at all
+        for callback in described.iter() {
+            let quoted = format!("\"{}\"", callback);
+            assert!(sources.iter().any(|source| source.contains(&quoted)), "\"{}\" is described but never triggered", callback);
+        }
+    }
+
+    #[test]
+    fn everyReferencedDefinitionExists() {
+        let definitions = protocolDefinitions().into_iter().map(|(name, _)| name).collect::<BTreeSet<&str>>();
+
+        for (command, info) in commands() {
+            if let Some(info) = info {
+                assert!(definitions.contains(info), "\"{}\" takes {}, which isn't defined", command, info);
+            }
+        }
+        for (callback, item) in callbacks() {
+            assert!(definitions.contains(item), "\"{}\" sends {}, which isn't defined", callback, item);
+        }
+    }
+}
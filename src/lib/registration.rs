@@ -0,0 +1,85 @@
+use std::{any::TypeId, collections::HashMap, sync::Arc};
+
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::*,
+    reflect::{GetTypeRegistration, TypePath},
+};
+use serde_json::Value;
+
+use super::metadata::FieldMetadata;
+
+// Replaces the reflection-based inspector output for a component
+pub type EditorSerializer = Arc<dyn Fn(&dyn Reflect) -> Option<Value> + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct EditorComponentInfo {
+    pub displayName: Option<String>,
+    pub category: Option<String>,
+    pub icon: Option<String>, // icon name the frontend resolves
+    pub serializer: Option<EditorSerializer>,
+    // Inspector metadata keyed by reflection path, overriding whatever the reflection attributes say
+    pub fields: HashMap<String, FieldMetadata>,
+}
+
+impl EditorComponentInfo {
+    pub fn named(displayName: &str) -> Self {
+        Self {
+            displayName: Some(displayName.to_string()),
+            ..default()
+        }
+    }
+
+    pub fn withCategory(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    pub fn withIcon(mut self, icon: &str) -> Self {
+        self.icon = Some(icon.to_string());
+        self
+    }
+
+    pub fn withField(mut self, path: &str, metadata: FieldMetadata) -> Self {
+        self.fields.insert(path.to_string(), metadata);
+        self
+    }
+
+    pub fn withSerializer<T: Reflect>(mut self, serializer: fn(&T) -> Value) -> Self {
+        self.serializer = Some(Arc::new(move |value: &dyn Reflect| value.downcast_ref::<T>().map(serializer)));
+        self
+    }
+}
+
+// Components the game registered with the editor, by type
+#[derive(Resource, Default)]
+pub struct EditorComponents {
+    pub components: HashMap<TypeId, EditorComponentInfo>,
+}
+
+impl EditorComponents {
+    pub fn get(&self, typeId: TypeId) -> Option<&EditorComponentInfo> {
+        self.components.get(&typeId)
+    }
+}
+
+pub trait EditorAppExt {
+    // Makes a game component show up in the inspector and in saved scenes
+    fn registerEditorComponent<T: Component + GetTypeRegistration + FromReflect + TypePath>(&mut self) -> &mut Self;
+    fn registerEditorComponentWith<T: Component + GetTypeRegistration + FromReflect + TypePath>(&mut self, info: EditorComponentInfo) -> &mut Self;
+}
+
+impl EditorAppExt for App {
+    fn registerEditorComponent<T: Component + GetTypeRegistration + FromReflect + TypePath>(&mut self) -> &mut Self {
+        self.registerEditorComponentWith::<T>(EditorComponentInfo::default())
+    }
+
+    fn registerEditorComponentWith<T: Component + GetTypeRegistration + FromReflect + TypePath>(&mut self, info: EditorComponentInfo) -> &mut Self {
+        // Registering `ReflectComponent` here as well means forgetting `#[reflect(Component)]` on the game side doesn't hide the component
+        self.register_type::<T>()
+            .register_type_data::<T, ReflectComponent>();
+
+        self.world_mut().get_resource_or_init::<EditorComponents>().components.insert(TypeId::of::<T>(), info);
+        self
+    }
+}
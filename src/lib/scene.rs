@@ -0,0 +1,333 @@
+use std::{any::TypeId, collections::{BTreeMap, HashMap}};
+
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    pbr::{MeshMaterial3d, StandardMaterial},
+    prelude::*,
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        TypeRegistry,
+    },
+    render::{
+        mesh::{Indices, PrimitiveTopology::TriangleList, VertexAttributeValues},
+        primitives::Aabb,
+        render_asset::RenderAssetUsages,
+        sync_world::SyncToRenderWorld,
+    },
+    utils::HashSet,
+};
+use bevy_mod_outline::{
+    OutlineStencil,
+    OutlineVolume,
+};
+use bevy_picking::mesh_picking::RayCastPickable;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeSeed;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    EditorConfiguration,
+    EditorCursor,
+    EditorId,
+    RotationCamera,
+};
+
+use super::{layers::{EditorLayer, EditorLayers}, selection::SelectionOutlineWidth};
+
+// Components that are either derived every frame, rebuilt from other saved data (hierarchy, assets) or only matter while the editor is running
+static UnsavedTypes: Lazy<HashSet<TypeId>> = Lazy::new(|| {
+    let mut set = HashSet::new();
+
+    set.insert(TypeId::of::<Aabb>());
+    set.insert(TypeId::of::<Children>());
+    set.insert(TypeId::of::<EditorId>());
+    set.insert(TypeId::of::<GlobalTransform>());
+    set.insert(TypeId::of::<InheritedVisibility>());
+    set.insert(TypeId::of::<Mesh3d>());
+    set.insert(TypeId::of::<MeshMaterial3d<StandardMaterial>>());
+    set.insert(TypeId::of::<Parent>());
+    set.insert(TypeId::of::<SyncToRenderWorld>());
+    set.insert(TypeId::of::<ViewVisibility>());
+
+    set
+});
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SavedMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub uvs: Option<Vec<[f32; 2]>>,
+    pub indices: Option<Vec<u32>>,
+}
+
+// Texture slots are not saved yet, as images don't have a saved representation either
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SavedMaterial {
+    pub baseColour: [f32; 4],
+    pub emissive: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub reflectance: f32,
+    pub unlit: bool,
+    pub doubleSided: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SavedEntity {
+    pub id: u64, // `EditorId` of the entity
+    pub parent: Option<u64>,
+    // Keys into `SavedScene::meshes` and `SavedScene::materials`
+    pub mesh: Option<String>,
+    pub material: Option<String>,
+    // Reflection-serialized components, keyed by their type path
+    pub components: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SavedScene {
+    pub entities: Vec<SavedEntity>,
+    pub meshes: BTreeMap<String, SavedMesh>,
+    pub materials: BTreeMap<String, SavedMaterial>,
+    #[serde(default)]
+    pub layers: Vec<EditorLayer>,
+}
+
+impl SavedMesh {
+    pub fn fromMesh(mesh: &Mesh) -> Option<Self> {
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { return None; };
+
+        Some(Self {
+            positions: positions.clone(),
+            normals: match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+                Some(VertexAttributeValues::Float32x3(normals)) => Some(normals.clone()),
+                _ => None,
+            },
+            uvs: match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+                Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs.clone()),
+                _ => None,
+            },
+            indices: mesh.indices().map(|indices| indices.iter().map(|index| index as u32).collect()),
+        })
+    }
+
+    pub fn toMesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(TriangleList, RenderAssetUsages::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+
+        if let Some(normals) = &self.normals {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.clone());
+        }
+        if let Some(uvs) = &self.uvs {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs.clone());
+        }
+        if let Some(indices) = &self.indices {
+            mesh.insert_indices(Indices::U32(indices.clone()));
+        }
+
+        return mesh;
+    }
+}
+
+impl SavedMaterial {
+    pub fn fromMaterial(material: &StandardMaterial) -> Self {
+        let baseColour = LinearRgba::from(material.base_color);
+        let emissive = material.emissive;
+
+        Self {
+            baseColour: [baseColour.red, baseColour.green, baseColour.blue, baseColour.alpha],
+            emissive: [emissive.red, emissive.green, emissive.blue, emissive.alpha],
+            metallic: material.metallic,
+            roughness: material.perceptual_roughness,
+            reflectance: material.reflectance,
+            unlit: material.unlit,
+            doubleSided: material.double_sided,
+        }
+    }
+
+    pub fn toMaterial(&self) -> StandardMaterial {
+        let [r, g, b, a] = self.baseColour;
+        let [er, eg, eb, ea] = self.emissive;
+
+        StandardMaterial {
+            base_color: Color::linear_rgba(r, g, b, a),
+            emissive: LinearRgba::new(er, eg, eb, ea),
+            metallic: self.metallic,
+            perceptual_roughness: self.roughness,
+            reflectance: self.reflectance,
+            unlit: self.unlit,
+            double_sided: self.doubleSided,
+            ..default()
+        }
+    }
+}
+
+// Assets are keyed by their contents rather than their `AssetId`, which keeps keys the same between sessions and deduplicates identical assets
+fn contentKey<T: serde::Serialize>(value: &T) -> String {
+    // FNV-1a, as it's stable across platforms and compiler versions
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in serde_json::to_string(value).unwrap_or_default().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    return format!("{:016x}", hash);
+}
+
+pub fn savedId(world: &World, entity: Entity) -> u64 {
+    world.get::<EditorId>(entity).map_or(entity.to_bits(), |id| id.0)
+}
+
+// Everything the user placed in the scene, as opposed to the editor camera and UI (such as the fps overlay)
+pub fn isSceneEntity(entity: EntityRef) -> bool {
+    entity.contains::<Transform>() && !entity.contains::<RotationCamera>() && !entity.contains::<EditorCursor>() && !entity.contains::<Node>()
+}
+
+pub fn serializeComponents(world: &World, entity: Entity, registry: &TypeRegistry) -> BTreeMap<String, Value> {
+    let mut components = BTreeMap::new();
+
+    for component in world.inspect_entity(entity) {
+        let Some(typeId) = component.type_id() else { continue; };
+        if UnsavedTypes.contains(&typeId) { continue; }
+
+        let Some(registration) = registry.get(typeId) else { continue; };
+        let Some(reflectComponent) = registration.data::<ReflectComponent>() else { continue; };
+        let Some(value) = reflectComponent.reflect(world.entity(entity)) else { continue; };
+
+        // Components that can't be serialized (e.g. ones holding handles or other opaque values) are left out
+        if let Ok(serialized) = serde_json::to_value(TypedReflectSerializer::new(value.as_partial_reflect(), registry)) {
+            components.insert(registration.type_info().type_path().to_string(), serialized);
+        }
+    }
+
+    return components;
+}
+
+pub fn captureScene(world: &World) -> SavedScene {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let meshes = world.resource::<Assets<Mesh>>();
+    let materials = world.resource::<Assets<StandardMaterial>>();
+
+    let mut scene = SavedScene {
+        layers: world.resource::<EditorLayers>().layers.clone(),
+        ..default()
+    };
+
+    for entity in world.iter_entities().filter(|entity| isSceneEntity(*entity)) {
+        let mut savedEntity = SavedEntity {
+            id: savedId(world, entity.id()),
+            parent: entity.get::<Parent>().map(|parent| savedId(world, parent.get())),
+            components: serializeComponents(world, entity.id(), &registry),
+            ..default()
+        };
+
+        if let Some(mesh) = entity.get::<Mesh3d>().and_then(|mesh3d| meshes.get(&mesh3d.0)).and_then(SavedMesh::fromMesh) {
+            let key = contentKey(&mesh);
+            scene.meshes.insert(key.clone(), mesh);
+            savedEntity.mesh = Some(key);
+        }
+        if let Some(material) = entity.get::<MeshMaterial3d<StandardMaterial>>().and_then(|meshMaterial| materials.get(&meshMaterial.0)) {
+            let material = SavedMaterial::fromMaterial(material);
+            let key = contentKey(&material);
+            scene.materials.insert(key.clone(), material);
+            savedEntity.material = Some(key);
+        }
+
+        scene.entities.push(savedEntity);
+    }
+    // Keeps saved files stable for version control
+    scene.entities.sort_by_key(|entity| entity.id);
+
+    return scene;
+}
+
+pub fn deserializeComponents(entity: &mut EntityWorldMut, components: &BTreeMap<String, Value>, registry: &TypeRegistry) -> Vec<String> {
+    let mut errors = vec![];
+
+    for (typePath, value) in components.iter() {
+        let Some(registration) = registry.get_with_type_path(typePath) else {
+            errors.push(format!("Unknown component type {}", typePath));
+            continue;
+        };
+        let Some(reflectComponent) = registration.data::<ReflectComponent>() else { continue; };
+
+        match TypedReflectDeserializer::new(registration, registry).deserialize(value) {
+            Ok(component) => reflectComponent.apply_or_insert(entity, &*component, registry),
+            Err(e) => errors.push(format!("Failed to load {}: {}", typePath, e)),
+        }
+    }
+
+    return errors;
+}
+
+pub fn clearScene(world: &mut World) {
+    let entities = world.iter_entities()
+        .filter(|entity| isSceneEntity(*entity))
+        .map(|entity| entity.id())
+        .collect::<Vec<Entity>>();
+
+    for entity in entities {
+        if let Ok(entityMut) = world.get_entity_mut(entity) {
+            entityMut.despawn_recursive();
+        }
+    }
+}
+
+// Replaces all scene entities in the world with the saved ones, returning the saved ID -> entity mapping and any non-fatal errors
+pub fn restoreScene(world: &mut World, scene: &SavedScene) -> (HashMap<u64, Entity>, Vec<String>) {
+    clearScene(world);
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let selectionColour = world.query::<&EditorConfiguration>().get_single(world).map(|config| config.selection.selectionColour).unwrap_or(Color::WHITE);
+
+    let meshHandles = scene.meshes.iter()
+        .map(|(key, mesh)| (key.clone(), world.resource_mut::<Assets<Mesh>>().add(mesh.toMesh())))
+        .collect::<HashMap<String, Handle<Mesh>>>();
+    let materialHandles = scene.materials.iter()
+        .map(|(key, material)| (key.clone(), world.resource_mut::<Assets<StandardMaterial>>().add(material.toMaterial())))
+        .collect::<HashMap<String, Handle<StandardMaterial>>>();
+
+    world.resource_mut::<EditorLayers>().layers = scene.layers.clone();
+
+    let mut entityMap: HashMap<u64, Entity> = HashMap::new();
+    let mut errors = vec![];
+
+    for savedEntity in scene.entities.iter() {
+        let mut entity = world.spawn(EditorId(savedEntity.id));
+        errors.extend(deserializeComponents(&mut entity, &savedEntity.components, &registry));
+
+        if let Some(mesh) = savedEntity.mesh.as_ref().and_then(|key| meshHandles.get(key)) {
+            entity.insert(Mesh3d(mesh.clone()));
+        }
+        if let Some(material) = savedEntity.material.as_ref().and_then(|key| materialHandles.get(key)) {
+            entity.insert(MeshMaterial3d(material.clone()));
+        }
+
+        // Outlines aren't reflected, so pickable meshes get their selection outline back here
+        if entity.contains::<Mesh3d>() && entity.contains::<RayCastPickable>() {
+            entity.insert((
+                OutlineStencil {
+                    enabled: true,
+                    offset: 0.,
+                },
+                OutlineVolume {
+                    colour: selectionColour,
+                    width: SelectionOutlineWidth,
+                    visible: false,
+                },
+            ));
+        }
+
+        entityMap.insert(savedEntity.id, entity.id());
+    }
+
+    for savedEntity in scene.entities.iter() {
+        let Some(parent) = savedEntity.parent.and_then(|parent| entityMap.get(&parent)) else { continue; };
+        let child = entityMap[&savedEntity.id];
+
+        world.entity_mut(*parent).add_child(child);
+    }
+
+    return (entityMap, errors);
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::scene::{SavedEntity, SavedScene};
+
+// Property paths are "parent", "mesh", "material" or "<component type path>/<field>/<field>...", with "/" and "~" in keys escaped like in JSON pointers
+type PropertyMap = BTreeMap<String, Value>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PropertyChange {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntityDiff {
+    pub id: u64,
+    pub kind: ChangeKind,
+    pub properties: Vec<PropertyChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SceneDiff {
+    pub entities: Vec<EntityDiff>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ConflictKind {
+    // Both sides changed the same property differently
+    Property,
+    // The entity was modified on one side and removed on the other, the whole entities are reported and an empty path is used
+    ModifiedRemoved,
+    RemovedModified,
+    // The merged parent doesn't exist anymore, so the entity was moved to the root
+    MissingParent,
+}
+
+// Values are None where the property or entity doesn't exist on that side
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PropertyConflict {
+    pub id: u64,
+    pub kind: ConflictKind,
+    pub path: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+// Conflicting properties are resolved to "ours" in the merged scene, so it is always loadable
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SceneMerge {
+    pub scene: SavedScene,
+    pub conflicts: Vec<PropertyConflict>,
+}
+
+fn escapeKey(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescapeKey(key: &str) -> String {
+    key.replace("~1", "/").replace("~0", "~")
+}
+
+// Externally tagged enums serialize as `{ "Variant": ... }`, merging the fields of two different variants would make up an invalid value
+fn isEnumVariant(fields: &Map<String, Value>) -> bool {
+    fields.len() == 1 && fields.keys().next().is_some_and(|key| key.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+fn flattenValue(path: String, value: &Value, properties: &mut PropertyMap) {
+    match value {
+        Value::Object(fields) if !fields.is_empty() && !isEnumVariant(fields) => {
+            for (key, field) in fields.iter() {
+                flattenValue(format!("{}/{}", path, escapeKey(key)), field, properties);
+            }
+        },
+        // Arrays (vectors, quaternions, colours...) and enums are compared as a whole
+        _ => { properties.insert(path, value.clone()); },
+    }
+}
+
+fn flattenEntity(entity: &SavedEntity) -> PropertyMap {
+    let mut properties = PropertyMap::new();
+
+    if let Some(parent) = entity.parent {
+        properties.insert("parent".to_string(), parent.into());
+    }
+    if let Some(mesh) = &entity.mesh {
+        properties.insert("mesh".to_string(), mesh.clone().into());
+    }
+    if let Some(material) = &entity.material {
+        properties.insert("material".to_string(), material.clone().into());
+    }
+    for (typePath, value) in entity.components.iter() {
+        flattenValue(escapeKey(typePath), value, &mut properties);
+    }
+
+    return properties;
+}
+
+fn insertValue(target: &mut Map<String, Value>, keys: &[String], value: Value) {
+    let Some((key, rest)) = keys.split_first() else { return; };
+
+    if rest.is_empty() {
+        target.insert(key.clone(), value);
+        return;
+    }
+
+    let child = target.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        *child = Value::Object(Map::new());
+    }
+    if let Value::Object(childMap) = child {
+        insertValue(childMap, rest, value);
+    }
+}
+
+fn unflattenEntity(id: u64, properties: &PropertyMap) -> SavedEntity {
+    let mut entity = SavedEntity { id, ..Default::default() };
+    let mut components = Map::new();
+
+    for (path, value) in properties.iter() {
+        match path.as_str() {
+            "parent" => entity.parent = value.as_u64(),
+            "mesh" => entity.mesh = value.as_str().map(str::to_string),
+            "material" => entity.material = value.as_str().map(str::to_string),
+            _ => {
+                let keys = path.split('/').map(unescapeKey).collect::<Vec<String>>();
+                insertValue(&mut components, &keys, value.clone());
+            },
+        }
+    }
+
+    entity.components = components.into_iter().collect();
+    return entity;
+}
+
+fn diffProperties(before: &PropertyMap, after: &PropertyMap) -> Vec<PropertyChange> {
+    let paths = before.keys().chain(after.keys()).collect::<BTreeSet<&String>>();
+
+    paths.into_iter().filter_map(|path| {
+        let (old, new) = (before.get(path), after.get(path));
+        if old == new { return None; }
+
+        Some(PropertyChange {
+            path: path.clone(),
+            before: old.cloned(),
+            after: new.cloned(),
+        })
+    }).collect()
+}
+
+fn entityValue(id: u64, properties: &PropertyMap) -> Value {
+    serde_json::to_value(unflattenEntity(id, properties)).unwrap_or_default()
+}
+
+fn entityMap(scene: &SavedScene) -> BTreeMap<u64, PropertyMap> {
+    scene.entities.iter().map(|entity| (entity.id, flattenEntity(entity))).collect()
+}
+
+pub fn diffScenes(before: &SavedScene, after: &SavedScene) -> SceneDiff {
+    let (beforeEntities, afterEntities) = (entityMap(before), entityMap(after));
+    let ids = beforeEntities.keys().chain(afterEntities.keys()).copied().collect::<BTreeSet<u64>>();
+    let empty = PropertyMap::new();
+
+    let entities = ids.into_iter().filter_map(|id| {
+        let (old, new) = (beforeEntities.get(&id), afterEntities.get(&id));
+        let kind = match (old, new) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            _ => ChangeKind::Modified,
+        };
+
+        let properties = diffProperties(old.unwrap_or(&empty), new.unwrap_or(&empty));
+        if kind == ChangeKind::Modified && properties.is_empty() { return None; }
+
+        Some(EntityDiff { id, kind, properties })
+    }).collect();
+
+    SceneDiff { entities }
+}
+
+fn mergeProperties(id: u64, base: &PropertyMap, ours: &PropertyMap, theirs: &PropertyMap, conflicts: &mut Vec<PropertyConflict>) -> PropertyMap {
+    let paths = base.keys().chain(ours.keys()).chain(theirs.keys()).collect::<BTreeSet<&String>>();
+    let mut merged = PropertyMap::new();
+
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+
+        let value = if o == t || t == b {
+            o
+        } else if o == b {
+            t
+        } else {
+            conflicts.push(PropertyConflict {
+                id,
+                kind: ConflictKind::Property,
+                path: path.clone(),
+                base: b.cloned(),
+                ours: o.cloned(),
+                theirs: t.cloned(),
+            });
+            o
+        };
+
+        if let Some(value) = value {
+            merged.insert(path.clone(), value.clone());
+        }
+    }
+
+    return merged;
+}
+
+pub fn mergeScenes(base: &SavedScene, ours: &SavedScene, theirs: &SavedScene) -> SceneMerge {
+    let (baseEntities, ourEntities, theirEntities) = (entityMap(base), entityMap(ours), entityMap(theirs));
+    let empty = PropertyMap::new();
+    let mut conflicts = vec![];
+
+    // Keeps our ordering, with entities only they added appended at the end
+    let mut ids = ours.entities.iter().map(|entity| entity.id).collect::<Vec<u64>>();
+    ids.extend(theirs.entities.iter().map(|entity| entity.id).filter(|id| !ourEntities.contains_key(id)));
+
+    let mut entities = vec![];
+    for id in ids {
+        let (b, o, t) = (baseEntities.get(&id), ourEntities.get(&id), theirEntities.get(&id));
+
+        let merged = match (b, o, t) {
+            // Removed on one side and modified on the other is a conflict, resolved to ours like any other
+            (Some(b), Some(o), None) => {
+                if o != b {
+                    conflicts.push(PropertyConflict {
+                        id,
+                        kind: ConflictKind::ModifiedRemoved,
+                        path: String::new(),
+                        base: Some(entityValue(id, b)),
+                        ours: Some(entityValue(id, o)),
+                        theirs: None,
+                    });
+                    Some(o.clone())
+                } else {
+                    None
+                }
+            },
+            (Some(b), None, Some(t)) => {
+                if t != b {
+                    conflicts.push(PropertyConflict {
+                        id,
+                        kind: ConflictKind::RemovedModified,
+                        path: String::new(),
+                        base: Some(entityValue(id, b)),
+                        ours: None,
+                        theirs: Some(entityValue(id, t)),
+                    });
+                }
+                None
+            },
+            (b, Some(o), Some(t)) => Some(mergeProperties(id, b.unwrap_or(&empty), o, t, &mut conflicts)),
+            (_, Some(o), None) | (_, None, Some(o)) => Some(o.clone()),
+            (_, None, None) => None,
+        };
+
+        if let Some(properties) = merged {
+            entities.push(unflattenEntity(id, &properties));
+        }
+    }
+
+    // One side may have removed an entity the other side parented something to
+    let mergedIds = entities.iter().map(|entity| entity.id).collect::<BTreeSet<u64>>();
+    for entity in entities.iter_mut() {
+        if !entity.parent.is_some_and(|parent| !mergedIds.contains(&parent)) { continue; }
+
+        let id = entity.id;
+        let parentOf = |entities: &BTreeMap<u64, PropertyMap>| entities.get(&id).and_then(|properties| properties.get("parent")).cloned();
+        conflicts.push(PropertyConflict {
+            id,
+            kind: ConflictKind::MissingParent,
+            path: "parent".to_string(),
+            base: parentOf(&baseEntities),
+            ours: parentOf(&ourEntities),
+            theirs: parentOf(&theirEntities),
+        });
+        entity.parent = None;
+    }
+
+    // Assets are keyed by content, so the same key always refers to the same asset on every side
+    // Layers are merged as a whole, taking whichever side changed them, ours if both did
+    let layers = if ours.layers == base.layers { theirs.layers.clone() } else { ours.layers.clone() };
+    let mut scene = SavedScene { entities, layers, ..Default::default() };
+    for source in [base, ours, theirs] {
+        for entity in scene.entities.iter() {
+            if let Some(mesh) = entity.mesh.as_ref().and_then(|key| source.meshes.get(key)) {
+                scene.meshes.insert(entity.mesh.clone().unwrap(), mesh.clone());
+            }
+            if let Some(material) = entity.material.as_ref().and_then(|key| source.materials.get(key)) {
+                scene.materials.insert(entity.material.clone().unwrap(), material.clone());
+            }
+        }
+    }
+
+    SceneMerge { scene, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entity(id: u64, x: f32) -> SavedEntity {
+        SavedEntity {
+            id,
+            components: [("bevy_transform::components::transform::Transform".to_string(), json!({ "translation": [x, 0., 0.] }))].into(),
+            ..Default::default()
+        }
+    }
+
+    fn scene(entities: Vec<SavedEntity>) -> SavedScene {
+        SavedScene { entities, ..Default::default() }
+    }
+
+    fn ids(scene: &SavedScene) -> Vec<u64> {
+        scene.entities.iter().map(|entity| entity.id).collect()
+    }
+
+    #[test]
+    fn diffReportsAddedRemovedAndModified() {
+        let before = scene(vec![entity(1, 0.), entity(2, 0.), entity(3, 0.)]);
+        let after = scene(vec![entity(1, 0.), entity(2, 5.), entity(4, 0.)]);
+
+        let diff = diffScenes(&before, &after);
+        let kinds = diff.entities.iter().map(|entity| (entity.id, entity.kind)).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(2, ChangeKind::Modified), (3, ChangeKind::Removed), (4, ChangeKind::Added)]);
+
+        let modified = &diff.entities[0].properties;
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].path, "bevy_transform::components::transform::Transform/translation");
+        assert_eq!(modified[0].before, Some(json!([0., 0., 0.])));
+        assert_eq!(modified[0].after, Some(json!([5., 0., 0.])));
+    }
+
+    #[test]
+    fn diffOfIdenticalScenesIsEmpty() {
+        let before = scene(vec![entity(1, 0.)]);
+        assert_eq!(diffScenes(&before, &before.clone()), SceneDiff::default());
+    }
+
+    #[test]
+    fn mergeTakesAdditionsFromBothSides() {
+        let base = scene(vec![entity(1, 0.)]);
+        let ours = scene(vec![entity(1, 0.), entity(2, 0.)]);
+        let theirs = scene(vec![entity(1, 0.), entity(3, 0.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(ids(&merge.scene), vec![1, 2, 3]);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn mergeTakesCleanRemovalsFromBothSides() {
+        let base = scene(vec![entity(1, 0.), entity(2, 0.), entity(3, 0.)]);
+        let ours = scene(vec![entity(1, 0.), entity(3, 0.)]);
+        let theirs = scene(vec![entity(1, 0.), entity(2, 0.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(ids(&merge.scene), vec![1]);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn mergeTakesModificationsFromEitherSide() {
+        let base = scene(vec![entity(1, 0.), entity(2, 0.)]);
+        let ours = scene(vec![entity(1, 1.), entity(2, 0.)]);
+        let theirs = scene(vec![entity(1, 0.), entity(2, 2.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(merge.scene.entities, vec![entity(1, 1.), entity(2, 2.)]);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn mergeResolvesPropertyConflictsToOurs() {
+        let base = scene(vec![entity(1, 0.)]);
+        let ours = scene(vec![entity(1, 1.)]);
+        let theirs = scene(vec![entity(1, 2.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(merge.scene.entities, vec![entity(1, 1.)]);
+        assert_eq!(merge.conflicts, vec![PropertyConflict {
+            id: 1,
+            kind: ConflictKind::Property,
+            path: "bevy_transform::components::transform::Transform/translation".to_string(),
+            base: Some(json!([0., 0., 0.])),
+            ours: Some(json!([1., 0., 0.])),
+            theirs: Some(json!([2., 0., 0.])),
+        }]);
+    }
+
+    #[test]
+    fn mergeKeepsEntityWeModifiedAndTheyRemoved() {
+        let base = scene(vec![entity(1, 0.)]);
+        let ours = scene(vec![entity(1, 1.)]);
+        let theirs = scene(vec![]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(merge.scene.entities, vec![entity(1, 1.)]);
+        assert_eq!(merge.conflicts, vec![PropertyConflict {
+            id: 1,
+            kind: ConflictKind::ModifiedRemoved,
+            path: String::new(),
+            base: Some(serde_json::to_value(entity(1, 0.)).unwrap()),
+            ours: Some(serde_json::to_value(entity(1, 1.)).unwrap()),
+            theirs: None,
+        }]);
+    }
+
+    #[test]
+    fn mergeDropsEntityWeRemovedAndTheyModified() {
+        let base = scene(vec![entity(1, 0.)]);
+        let ours = scene(vec![]);
+        let theirs = scene(vec![entity(1, 2.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert!(merge.scene.entities.is_empty());
+        assert_eq!(merge.conflicts, vec![PropertyConflict {
+            id: 1,
+            kind: ConflictKind::RemovedModified,
+            path: String::new(),
+            base: Some(serde_json::to_value(entity(1, 0.)).unwrap()),
+            ours: None,
+            theirs: Some(serde_json::to_value(entity(1, 2.)).unwrap()),
+        }]);
+    }
+
+    #[test]
+    fn mergeComparesEnumVariantsAsWhole() {
+        let projection = |value: Value| SavedEntity {
+            id: 1,
+            components: [("bevy_render::camera::projection::Projection".to_string(), value)].into(),
+            ..Default::default()
+        };
+        let base = scene(vec![projection(json!({ "Perspective": { "fov": 1. } }))]);
+        let ours = scene(vec![projection(json!({ "Perspective": { "fov": 2. } }))]);
+        let theirs = scene(vec![projection(json!({ "Orthographic": { "scale": 1. } }))]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(merge.scene.entities, ours.entities);
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].kind, ConflictKind::Property);
+        assert_eq!(merge.conflicts[0].path, "bevy_render::camera::projection::Projection");
+    }
+
+    #[test]
+    fn mergeMovesEntitiesWithRemovedParentToRoot() {
+        let base = scene(vec![entity(1, 0.), entity(2, 0.)]);
+        let ours = scene(vec![entity(1, 0.), SavedEntity { parent: Some(1), ..entity(2, 0.) }]);
+        let theirs = scene(vec![entity(2, 0.)]);
+
+        let merge = mergeScenes(&base, &ours, &theirs);
+        assert_eq!(merge.scene.entities, vec![entity(2, 0.)]);
+        assert_eq!(merge.conflicts, vec![PropertyConflict {
+            id: 2,
+            kind: ConflictKind::MissingParent,
+            path: "parent".to_string(),
+            base: None,
+            ours: Some(json!(1)),
+            theirs: None,
+        }]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::any::TypeId;
+
+use bevy::{prelude::*, reflect::TypeRegistry};
+use regex::Regex;
+
+use crate::{EditorId, EditorTags};
+
+use super::{
+    layers::OnEditorLayer,
+    properties::componentTypeId,
+};
+
+// A single term of a search query, every term has to match for an entity to be found
+enum SearchFilter {
+    NameContains(String), // lowercase, names are matched case-insensitively
+    NameMatches(Regex),
+    Has(TypeId),
+    Tag(String),
+    Layer(String),
+    Not(Box<SearchFilter>),
+}
+
+fn parseTerm(term: &str, registry: &TypeRegistry) -> Result<SearchFilter, String> {
+    if let Some(negated) = term.strip_prefix('!') {
+        return Ok(SearchFilter::Not(Box::new(parseTerm(negated, registry)?)));
+    }
+
+    let filter = match term.split_once(':') {
+        Some(("has", componentName)) => SearchFilter::Has(componentTypeId(registry, componentName)?),
+        Some(("tag", tag)) => SearchFilter::Tag(tag.to_string()),
+        Some(("layer", layer)) => SearchFilter::Layer(layer.to_string()),
+        Some(("re", pattern)) => SearchFilter::NameMatches(Regex::new(pattern).map_err(|e| format!("Invalid regex {}: {}", pattern, e))?),
+        _ => SearchFilter::NameContains(term.to_lowercase()),
+    };
+
+    return Ok(filter);
+}
+
+// Splits a query on whitespace, except within double quotes, which are left out of the terms.
+// Inside quotes, \" is a literal quote and any other backslash is kept as it is (for regexes)
+fn splitTerms(query: &str) -> Result<Vec<String>, String> {
+    let mut terms = vec![];
+    let mut term: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = query.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                term.get_or_insert_with(String::new);
+            },
+            '\\' if quoted && chars.as_str().starts_with('"') => {
+                chars.next();
+                term.get_or_insert_with(String::new).push('"');
+            },
+            c if c.is_whitespace() && !quoted => terms.extend(term.take()),
+            c => term.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err("Missing closing quote in search query".to_string());
+    }
+    terms.extend(term);
+
+    return Ok(terms);
+}
+
+// Queries are whitespace separated terms, e.g. `lamp has:PointLight !has:Mesh3d tag:todo layer:"Set dressing" re:^Box\.\d+$`.
+// Plain words match part of the name, and any term can be negated with a leading "!". Terms with spaces are quoted
+fn parseQuery(query: &str, registry: &TypeRegistry) -> Result<Vec<SearchFilter>, String> {
+    splitTerms(query)?.iter().map(|term| parseTerm(term, registry)).collect()
+}
+
+fn matchesFilter(entity: EntityRef, filter: &SearchFilter) -> bool {
+    let name = entity.get::<Name>().map_or("", |name| name.as_str());
+
+    match filter {
+        SearchFilter::NameContains(text) => name.to_lowercase().contains(text),
+        SearchFilter::NameMatches(regex) => regex.is_match(name),
+        SearchFilter::Has(typeId) => entity.contains_type_id(*typeId),
+        SearchFilter::Tag(tag) => entity.get::<EditorTags>().is_some_and(|tags| tags.0.contains(tag)),
+        SearchFilter::Layer(layer) => entity.get::<OnEditorLayer>().is_some_and(|onLayer| onLayer.0 == *layer),
+        SearchFilter::Not(filter) => !matchesFilter(entity, filter),
+    }
+}
+
+// Scene entities matching every term of the query
+pub fn searchEntities(world: &World, query: &str) -> Result<Vec<Entity>, String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let filters = parseQuery(query, &registry)?;
+
+    let found = world.iter_entities()
+        .filter(|entity| entity.contains::<EditorId>())
+        .filter(|entity| filters.iter().all(|filter| matchesFilter(*entity, filter)))
+        .map(|entity| entity.id())
+        .collect::<Vec<Entity>>();
+
+    return Ok(found);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(query: &str) -> Vec<String> {
+        splitTerms(query).unwrap()
+    }
+
+    fn testWorld() -> World {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        registry.write().register::<Transform>();
+        registry.write().register::<PointLight>();
+        world.insert_resource(registry);
+
+        world.spawn((EditorId(1), Name::new("Desk lamp"), Transform::default(), PointLight::default(), EditorTags(vec!["todo".to_string()]), OnEditorLayer("Lighting".to_string())));
+        world.spawn((EditorId(2), Name::new("Box.001"), Transform::default(), OnEditorLayer("Set dressing".to_string())));
+        world.spawn((EditorId(3), Name::new("Box.002"), Transform::default(), EditorTags(vec!["needs work".to_string()])));
+        // Not part of the scene
+        world.spawn((Name::new("Box.003"), Transform::default()));
+
+        return world;
+    }
+
+    fn search(world: &World, query: &str) -> Vec<String> {
+        let mut names = searchEntities(world, query).unwrap().into_iter()
+            .map(|entity| world.get::<Name>(entity).unwrap().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+        return names;
+    }
+
+    #[test]
+    fn splitsOnWhitespace() {
+        assert_eq!(terms("  lamp   has:PointLight "), vec!["lamp", "has:PointLight"]);
+        assert!(terms("   ").is_empty());
+    }
+
+    #[test]
+    fn quotedTermsKeepTheirSpaces() {
+        assert_eq!(terms(r#"layer:"Set dressing" "desk lamp" !tag:"needs work""#), vec!["layer:Set dressing", "desk lamp", "!tag:needs work"]);
+        assert_eq!(terms(r#"re:"Box \d" "say \"hi\"""#), vec![r"re:Box \d", r#"say "hi""#]);
+        assert_eq!(terms(r#"tag:"""#), vec!["tag:"]);
+        assert!(splitTerms(r#"layer:"Set dressing"#).is_err());
+    }
+
+    #[test]
+    fn matchesNamesCaseInsensitively() {
+        let world = testWorld();
+        assert_eq!(search(&world, "box"), vec!["Box.001", "Box.002"]);
+        assert_eq!(search(&world, r#""desk LAMP""#), vec!["Desk lamp"]);
+        assert_eq!(search(&world, "box !002"), vec!["Box.001"]);
+    }
+
+    #[test]
+    fn matchesComponents() {
+        let world = testWorld();
+        assert_eq!(search(&world, "has:PointLight"), vec!["Desk lamp"]);
+        assert_eq!(search(&world, "has:Transform !has:PointLight"), vec!["Box.001", "Box.002"]);
+        assert!(searchEntities(&world, "has:Nothing").is_err());
+    }
+
+    #[test]
+    fn matchesRegexes() {
+        let world = testWorld();
+        assert_eq!(search(&world, r"re:^Box\.\d+$"), vec!["Box.001", "Box.002"]);
+        assert_eq!(search(&world, r"!re:^Box"), vec!["Desk lamp"]);
+        assert!(searchEntities(&world, "re:(").is_err());
+    }
+
+    #[test]
+    fn matchesTagsAndLayers() {
+        let world = testWorld();
+        assert_eq!(search(&world, "tag:todo"), vec!["Desk lamp"]);
+        assert_eq!(search(&world, r#"tag:"needs work""#), vec!["Box.002"]);
+        assert_eq!(search(&world, r#"layer:"Set dressing""#), vec!["Box.001"]);
+        assert_eq!(search(&world, "!layer:Lighting"), vec!["Box.001", "Box.002"]);
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use bevy_mod_outline::OutlineVolume;
+use transform_gizmo_bevy::GizmoTarget;
+
+use super::{
+    components::{EditorHidden, EditorId, EditorLocked},
+    layers::{isOnSelectableLayer, EditorLayers, OnEditorLayer},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionMode {
+    Replace,
+    Add,
+    Remove,
+}
+
+// The selection as last sent to the frontend, in the order entities got selected, the last one being the active entity.
+// Entities with a `GizmoTarget` are what's actually selected, this only tracks what the frontend knows about
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub entities: Vec<Entity>,
+    pub requested: Vec<Entity>, // order the frontend last asked for, applied by `updateSelection`
+}
+
+impl Selection {
+    pub fn active(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+}
+
+// Outline widths of selected and hovered entities, hovering shows a thinner outline so the two can be told apart
+pub const SelectionOutlineWidth: f32 = 3.;
+pub const HoverOutlineWidth: f32 = 1.5;
+
+// The pickable entity under the cursor, if any
+#[derive(Resource, Default)]
+pub struct HoveredEntity(pub Option<Entity>);
+
+// Selected entities get a gizmo and their outline shown
+pub fn setSelected(world: &mut World, entity: Entity, selected: bool) {
+    let Ok(mut entityMut) = world.get_entity_mut(entity) else { return; };
+
+    if selected {
+        if !entityMut.contains::<GizmoTarget>() {
+            entityMut.insert(GizmoTarget::default());
+        }
+    } else {
+        entityMut.remove::<GizmoTarget>();
+    }
+    if let Some(mut outlineVolume) = entityMut.get_mut::<OutlineVolume>() {
+        outlineVolume.visible = selected;
+    }
+}
+
+pub fn selectedEntities(world: &mut World) -> Vec<Entity> {
+    world.query_filtered::<Entity, With<GizmoTarget>>().iter(world).collect()
+}
+
+// Whether an entity could be clicked in the viewport: part of the scene, not locked or hidden and not on a layer that can't be selected
+pub fn isSelectable(world: &World, entity: Entity) -> bool {
+    let Ok(entityRef) = world.get_entity(entity) else { return false; };
+
+    entityRef.contains::<EditorId>() && !entityRef.contains::<EditorLocked>() && !entityRef.contains::<EditorHidden>()
+        && world.get_resource::<EditorLayers>().map_or(true, |layers| isOnSelectableLayer(layers, entityRef.get::<OnEditorLayer>()))
+}
+
+// Changes the selection the same way clicking in the viewport does, removing without any entities clears the selection.
+// The last of the given entities becomes the active one
+pub fn selectEntities(world: &mut World, entities: &[Entity], mode: SelectionMode) -> Result<(), String> {
+    for entity in entities {
+        world.get_entity(*entity).map_err(|e| e.to_string())?;
+        // Anything can be deselected, e.g. entities locked while selected
+        if mode != SelectionMode::Remove && !isSelectable(world, *entity) {
+            return Err(format!("{} can't be selected", entity));
+        }
+    }
+
+    match mode {
+        SelectionMode::Replace => {
+            for selected in selectedEntities(world) {
+                if !entities.contains(&selected) { setSelected(world, selected, false); }
+            }
+            for entity in entities { setSelected(world, *entity, true); }
+        },
+        SelectionMode::Add => for entity in entities { setSelected(world, *entity, true); },
+        SelectionMode::Remove if entities.is_empty() => for selected in selectedEntities(world) { setSelected(world, selected, false); },
+        SelectionMode::Remove => for entity in entities { setSelected(world, *entity, false); },
+    }
+    if mode != SelectionMode::Remove {
+        world.resource_mut::<Selection>().requested = entities.to_vec();
+    }
+
+    Ok(())
+}
+
+// Brings `Selection` up to date with the selected entities, keeping the order of entities that stayed selected
+// and putting the ones last requested by the frontend at the end in its order. Returns whether anything changed
+pub fn updateSelection(world: &mut World) -> bool {
+    let current = selectedEntities(world);
+    let mut selection = world.resource_mut::<Selection>();
+    let requested = std::mem::take(&mut selection.requested);
+    let previous = selection.entities.clone();
+
+    let mut entities = previous.iter().copied().filter(|entity| current.contains(entity) && !requested.contains(entity)).collect::<Vec<Entity>>();
+    entities.extend(requested.iter().filter(|entity| current.contains(entity)));
+    let added = current.into_iter().filter(|entity| !entities.contains(entity)).collect::<Vec<Entity>>();
+    entities.extend(added);
+
+    if entities == previous { return false; }
+    selection.entities = entities;
+    return true;
+}
@@ -2,11 +2,29 @@
 
 mod lib {
     pub mod assetloader;
+    pub mod autosave;
     pub mod components;
+    pub mod duplication;
     pub mod editorconfig;
+    pub mod editorflags;
     pub mod editorvisibility;
     pub mod jscasting;
+    pub mod layers;
+    pub mod marquee;
+    pub mod materials;
+    pub mod metadata;
+    pub mod naming;
+    pub mod pivot;
+    pub mod hierarchy;
     pub mod history;
+    pub mod inspector;
+    pub mod properties;
+    pub mod protocol;
+    pub mod registration;
+    pub mod scene;
+    pub mod scenediff;
+    pub mod search;
+    pub mod selection;
 }
 mod wasm {
     pub mod definitions;
@@ -15,6 +33,8 @@ mod wasm {
     pub mod data;
 }
 mod systems {
+    #[cfg(target_arch = "wasm32")]
+    pub mod bridge;
     pub mod startup;
     pub mod update;
 }
@@ -22,6 +42,7 @@ mod systems {
 use lib::{
     components::*,
     editorconfig::EditorConfiguration,
+    registration::*,
 };
 use wasm::definitions::*;
 
@@ -58,17 +79,34 @@ fn main() {
             MeshPickingPlugin,
             TransformGizmoPlugin,
         ))
+        .register_type::<EditorId>()
+        .register_type::<EditorHidden>()
+        .register_type::<EditorLocked>()
+        .register_type::<lib::layers::OnEditorLayer>()
+        .init_resource::<EditorComponents>()
+        .init_resource::<lib::layers::EditorLayers>()
+        .init_resource::<lib::marquee::Marquee>()
+        .init_resource::<lib::selection::HoveredEntity>()
+        .init_resource::<lib::selection::Selection>()
+        .init_resource::<lib::pivot::PivotSettings>()
+        .init_resource::<lib::pivot::PivotDrag>()
+        .registerEditorComponentWith::<GlobalTransform>(EditorComponentInfo::named("Global Transform").withSerializer(lib::properties::globalTransformInfo))
+        .registerEditorComponentWith::<Name>(EditorComponentInfo::named("Name").withSerializer(lib::naming::nameInfo))
+        .registerEditorComponentWith::<EditorTags>(EditorComponentInfo::named("Tags").withCategory("Editor"))
         .insert_resource(MeshPickingSettings {
             require_markers: true,
             ..default()
         })
         .insert_resource(GizmoOptions {
             gizmo_orientation: GizmoOrientation::Local,
+            // Several selected entities share one gizmo, transforming them around their median point
+            group_targets: true,
             ..default()
         })
         
         .add_systems(Startup, (setup, setupDynamicAssets).chain())
-        .add_systems(Update, (mouseInteractions, keyboardInteractions, update).chain())
+        .add_systems(Update, (assignEditorIds, assignDefaultNames, mouseInteractions, marqueeSelection, hoverHighlight, keyboardInteractions, trackSelection, applyPivot, editorCursor, syncPivot, update).chain())
+        .add_systems(PostUpdate, lib::editorflags::hideEditorHidden.after(bevy::render::view::VisibilitySystems::CheckVisibility))
         
         .run();
 }
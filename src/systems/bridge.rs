@@ -0,0 +1,374 @@
+use std::{collections::HashSet, sync::Arc};
+
+use bevy::prelude::*;
+
+use crate::{
+    EditorCursor,
+    lib::{
+        editorflags::{setEditorFlag, EditorFlag},
+        hierarchy::*,
+        history::HistoryItem,
+        inspector::*,
+        jscasting::{asJsObject, serdeAsJsObject},
+        layers::*,
+        materials::*,
+        naming::renameEntity,
+        pivot::PivotSettings,
+        properties::*,
+        protocol::*,
+        scene::*,
+        search::searchEntities,
+        selection::{isSelectable, selectEntities, SelectionMode},
+    },
+    systems::update::{sendProperties, sendResources},
+    requestResult,
+    requestResultWith,
+    triggerInterfaceCallbacks,
+    wasm::data::*,
+};
+
+// Swaps the current scene out for a saved one, history entries point at entities that don't exist anymore afterwards, so history is cleared too
+// Whatever could be restored stays in place, the error lists everything that couldn't
+pub fn replaceScene(world: &mut World, scene: &SavedScene) -> Result<(), String> {
+    let (_, errors) = restoreScene(world, scene);
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.past.clear();
+        history.future.clear();
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+}
+
+fn setProperty(world: &mut World, info: &PropertyUpdateInfo) -> Result<(), String> {
+    let mut items = vec![];
+    for bits in info.targets() {
+        let result = entityFromBits(bits).and_then(|entity| {
+            let before = writeProperty(world, entity, &info.componentName, &info.property, &info.value)?;
+            Ok((entity, before))
+        });
+
+        match result {
+            Ok((entity, before)) => items.push(HistoryItem::Property {
+                entity,
+                componentName: info.componentName.clone(),
+                property: info.property.clone(),
+                before,
+                after: info.value.clone(),
+            }),
+            Err(e) => {
+                // Batch edits apply to all entities or none of them
+                for item in items.iter().rev() {
+                    if let HistoryItem::Property { entity, componentName, property, before, .. } = item {
+                        let _ = writeProperty(world, *entity, componentName, property, before);
+                    }
+                }
+                return Err(e);
+            },
+        }
+    }
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(if items.len() == 1 { items.remove(0) } else { HistoryItem::Batch(items) });
+    }
+
+    Ok(())
+}
+
+fn setMaterialProperty(world: &mut World, info: &MaterialUpdateInfo) -> Result<(), String> {
+    let entity = entityFromBits(info.entity)?;
+    let previous = if info.fork { Some(forkMaterial(world, entity)?) } else { None };
+    let material = entityMaterial(world, entity)?;
+
+    let before = match writeMaterialProperty(world, &material, &info.property, &info.value) {
+        Ok(before) => before,
+        Err(e) => {
+            // A failed edit shouldn't leave a fork behind
+            if let Some(previous) = previous {
+                world.entity_mut(entity).insert(MeshMaterial3d(previous));
+            }
+            return Err(e);
+        },
+    };
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(HistoryItem::Material {
+            entity,
+            material,
+            previous,
+            property: info.property.clone(),
+            before,
+            after: info.value.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+fn pushComponentHistory(world: &World, entity: Entity, componentName: &str, value: Box<dyn bevy::reflect::PartialReflect>, inserted: bool) {
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(HistoryItem::Component {
+            entity,
+            componentName: componentName.to_string(),
+            value: Arc::from(value),
+            inserted,
+        });
+    }
+}
+
+fn insertComponentFromInfo(world: &mut World, info: &ComponentUpdateInfo) -> Result<Entity, String> {
+    let entity = entityFromBits(info.entity)?;
+    let component = match &info.value {
+        Some(value) => {
+            let registry = world.resource::<AppTypeRegistry>().clone();
+            let registry = registry.read();
+            Some(deserializeComponent(&registry, &info.componentName, value)?)
+        },
+        None => None,
+    };
+
+    let inserted = insertComponent(world, entity, &info.componentName, component.as_deref())?;
+    pushComponentHistory(world, entity, &info.componentName, inserted, true);
+
+    Ok(entity)
+}
+
+fn removeComponentFromInfo(world: &mut World, info: &ComponentUpdateInfo) -> Result<Entity, String> {
+    let entity = entityFromBits(info.entity)?;
+    let removed = removeComponent(world, entity, &info.componentName)?;
+    pushComponentHistory(world, entity, &info.componentName, removed, false);
+
+    Ok(entity)
+}
+
+fn componentResult(callbackType: &str, world: &World, info: &ComponentUpdateInfo, result: Result<Entity, String>) {
+    // The inspector is refreshed right away, so it doesn't show a component that's gone or miss a new one
+    if let Ok(entity) = &result {
+        sendProperties(world, *entity);
+    }
+
+    requestResult(callbackType, info.requestId, result.map(|_| ()));
+}
+
+fn setResourceProperty(world: &mut World, info: &ResourceUpdateInfo) -> Result<(), String> {
+    let before = writeResourceProperty(world, &info.resourceName, &info.property, &info.value)?;
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(HistoryItem::Resource {
+            resourceName: info.resourceName.clone(),
+            property: info.property.clone(),
+            before,
+            after: info.value.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+fn rename(world: &mut World, info: &RenameInfo) -> Result<(), String> {
+    let entity = entityFromBits(info.entity)?;
+    let before = renameEntity(world, entity, &info.name)?;
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(HistoryItem::Property {
+            entity,
+            componentName: <Name as bevy::reflect::TypePath>::type_path().to_string(),
+            property: String::new(),
+            before: before.into(),
+            after: info.name.as_str().into(),
+        });
+    }
+
+    Ok(())
+}
+
+fn reparent(world: &mut World, info: &ReparentInfo) -> Result<(), String> {
+    let entity = entityFromBits(info.entity)?;
+    let parent = info.parent.map(entityFromBits).transpose()?;
+    let (previousParent, previousTransform, transform) = reparentEntity(world, entity, parent, info.keepWorldTransform.unwrap_or(true))?;
+
+    if let Ok(mut history) = world.resource::<RunnerWrapper>().history.write() {
+        history.future.clear();
+        history.past.push(HistoryItem::Reparent {
+            entity,
+            before: (previousParent, previousTransform),
+            after: (parent, transform),
+        });
+    }
+
+    Ok(())
+}
+
+fn watch(world: &mut World, info: &WatchInfo) -> Result<(), String> {
+    let entity = entityFromBits(info.entity.ok_or("No entity to watch")?)?;
+    world.get_entity(entity).map_err(|e| e.to_string())?;
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let components = match &info.components {
+        Some(names) => Some(names.iter().map(|name| componentTypeId(&registry, name)).collect::<Result<HashSet<_>, _>>()?),
+        None => None,
+    };
+
+    // The frontend gets fresh values to apply the deltas to
+    let sent = watchBaseline(world, entity, &components, &registry);
+    sendProperties(world, entity);
+
+    world.resource_mut::<InspectorWatches>().entities.insert(entity, WatchedEntity { components, sent });
+    Ok(())
+}
+
+// Sends every layer of the scene through the "layers" callback
+fn sendLayers(world: &World) {
+    let layers = &world.resource::<EditorLayers>().layers;
+    triggerInterfaceCallbacks("layers", layers.iter().map(serdeAsJsObject).collect());
+}
+
+fn layerResult(world: &World, requestId: Option<u64>, result: Result<(), String>) {
+    if result.is_ok() {
+        sendLayers(world);
+    }
+
+    requestResult("layerResult", requestId, result);
+}
+
+pub fn handleBridgeEvents(
+    world: &mut World,
+) {
+    let events = match world.resource::<RunnerWrapper>().events.write() {
+        Ok(mut events) => std::mem::take(&mut *events),
+        Err(_) => return,
+    };
+
+    for event in events {
+        match event {
+            BridgeEvent::SaveScene => {
+                triggerInterfaceCallbacks("scene", vec![serdeAsJsObject(&captureScene(world))]);
+            },
+            BridgeEvent::LoadScene(scene, requestId) => {
+                let result = replaceScene(world, &scene);
+                requestResult("loadSceneResult", requestId, result);
+            },
+            BridgeEvent::SetProperty(info) => {
+                let result = setProperty(world, &info);
+
+                requestResult("setPropertyResult", info.requestId, result);
+            },
+            BridgeEvent::InspectResources => sendResources(world),
+            BridgeEvent::SetResourceProperty(info) => {
+                let result = setResourceProperty(world, &info);
+
+                requestResult("setResourcePropertyResult", info.requestId, result);
+            },
+            BridgeEvent::Watch(info) => {
+                let result = watch(world, &info);
+                requestResult("watchResult", info.requestId, result);
+            },
+            BridgeEvent::Unwatch(info) => {
+                let mut watches = world.resource_mut::<InspectorWatches>();
+                let result = match info.entity.map(entityFromBits) {
+                    Some(Ok(entity)) => { watches.entities.remove(&entity); Ok(()) },
+                    Some(Err(e)) => Err(e),
+                    None => { watches.entities.clear(); Ok(()) },
+                };
+                requestResult("watchResult", info.requestId, result);
+            },
+            BridgeEvent::Rename(info) => {
+                let result = rename(world, &info);
+
+                requestResult("renameResult", info.requestId, result);
+            },
+            BridgeEvent::Reparent(info) => {
+                let result = reparent(world, &info);
+
+                requestResult("reparentResult", info.requestId, result);
+            },
+            BridgeEvent::Select(info, mode) => {
+                // The change goes back to the frontend through the "selection" callback like any other selection change
+                let result = info.entities.iter().map(|bits| entityFromBits(*bits)).collect::<Result<Vec<Entity>, String>>()
+                    .and_then(|entities| selectEntities(world, &entities, mode));
+                requestResult("selectionResult", info.requestId, result);
+            },
+            BridgeEvent::SetEditorFlag(info, flag) => {
+                let result = info.entities.iter().map(|bits| entityFromBits(*bits)).collect::<Result<Vec<Entity>, String>>()
+                    .and_then(|entities| setEditorFlag(world, &entities, flag, info.value));
+
+                requestResult(if flag == EditorFlag::Hidden { "setHiddenResult" } else { "setLockedResult" }, info.requestId, result);
+            },
+            BridgeEvent::ListLayers => sendLayers(world),
+            BridgeEvent::UpdateLayer(info) => {
+                updateLayer(world, &info.layer);
+                layerResult(world, info.requestId, Ok(()));
+            },
+            BridgeEvent::RemoveLayer(info) => {
+                let result = removeLayer(world, &info.layer.name);
+                layerResult(world, info.requestId, result);
+            },
+            BridgeEvent::AssignLayer(info) => {
+                let result = info.entities.iter().map(|bits| entityFromBits(*bits)).collect::<Result<Vec<Entity>, String>>()
+                    .and_then(|entities| assignLayer(world, &entities, info.layer.as_deref()));
+                layerResult(world, info.requestId, result);
+            },
+            BridgeEvent::Search(info) => {
+                let (found, error) = match searchEntities(world, &info.query) {
+                    Ok(found) => {
+                        // Locked and hidden entities are found, but can't be selected
+                        let selectable = found.iter().copied().filter(|entity| isSelectable(world, *entity)).collect::<Vec<Entity>>();
+                        let selected = if info.select { selectEntities(world, &selectable, SelectionMode::Replace) } else { Ok(()) };
+                        (found, selected.err().map(|e| format!("Failed to select the results: {}", e)))
+                    },
+                    Err(e) => (vec![], Some(e)),
+                };
+                requestResultWith("searchResult", info.requestId, error.map_or(Ok(()), Err), vec![
+                    ("entities", serdeAsJsObject(&found.iter().map(|entity| entity.to_bits()).collect::<Vec<u64>>()).into()),
+                ]);
+            },
+            // Both are sent back through the "pivot" callback once they changed
+            BridgeEvent::SetPivotMode(info) => {
+                world.resource_mut::<PivotSettings>().mode = info.mode;
+                requestResult("setPivotModeResult", info.requestId, Ok(()));
+            },
+            BridgeEvent::PlaceCursor(info) => {
+                let mut cursors = world.query_filtered::<&mut Transform, With<EditorCursor>>();
+                let result = cursors.get_single_mut(world)
+                    .map(|mut cursor| cursor.translation = Vec3::from_array(info.position))
+                    .map_err(|e| format!("Failed to find the 3D cursor: {}", e));
+                requestResult("placeCursorResult", info.requestId, result);
+            },
+            BridgeEvent::HierarchySnapshot => {
+                world.resource_mut::<HierarchyStream>().enabled = true;
+                triggerInterfaceCallbacks("hierarchy", vec![serdeAsJsObject(&hierarchySnapshot(world))]);
+            },
+            BridgeEvent::DescribeProtocol => {
+                let schema = protocolSchema(&world.resource::<AppTypeRegistry>().read());
+
+                triggerInterfaceCallbacks("protocol", vec![asJsObject(vec![
+                    ("typescript", protocolTypescript(&schema).into()),
+                    ("schema", serdeAsJsObject(&schema).into()),
+                ])]);
+            },
+            BridgeEvent::ListComponents => {
+                let components = insertableComponents(world);
+                triggerInterfaceCallbacks("insertableComponents", components.iter().map(serdeAsJsObject).collect());
+            },
+            BridgeEvent::InsertComponent(info) => {
+                let result = insertComponentFromInfo(world, &info);
+                componentResult("insertComponentResult", world, &info, result);
+            },
+            BridgeEvent::RemoveComponent(info) => {
+                let result = removeComponentFromInfo(world, &info);
+                componentResult("removeComponentResult", world, &info, result);
+            },
+            BridgeEvent::SetMaterialProperty(info) => {
+                let result = setMaterialProperty(world, &info);
+
+                requestResult("setMaterialPropertyResult", info.requestId, result);
+            },
+        }
+    }
+}
@@ -10,17 +10,44 @@ use bevy_mod_outline::{
 use transform_gizmo_bevy::prelude::*;
 use crate::{
     EditorConfiguration,
+    EditorCursor,
+    lib::{marquee::MarqueeOverlay, selection::SelectionOutlineWidth},
     RotationCamera,
 };
 #[cfg(target_arch = "wasm32")]
-use crate::consoleLog;
+use crate::{
+    consoleLog,
+    lib::{
+        autosave::*,
+        jscasting::asJsObject,
+    },
+    triggerInterfaceCallbacks,
+    wasm::data::RunnerWrapper,
+};
 
 pub fn setup(
     mut configStore: ResMut<GizmoConfigStore>,
     mut commands: Commands,
 ) {
     configStore.config_mut::<DefaultGizmoConfigGroup>().0.render_layers = RenderLayers::layer(0);
-    commands.spawn(EditorConfiguration::default());
+    let config = EditorConfiguration::default();
+    let selectionColour = config.selection.selectionColour;
+    commands.spawn(config);
+
+    commands.spawn((Transform::default(), EditorCursor));
+
+    // Box selection rectangle, only shown while dragging
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            border: UiRect::all(Val::Px(1.)),
+            ..default()
+        },
+        BorderColor(selectionColour),
+        BackgroundColor(selectionColour.with_alpha(0.15)),
+        Visibility::Hidden,
+        MarqueeOverlay,
+    ));
 
     commands.spawn((
         Camera3d::default(),
@@ -72,6 +99,7 @@ pub fn setupDynamicAssets(
     let mesh = meshes.add(Mesh::from(Capsule3d { radius: 3., half_length: 3. }));
 
     commands.spawn((
+        Name::new("Capsule"),
         Mesh3d(mesh),
         MeshMaterial3d(material.clone()),
         OutlineStencil {
@@ -80,10 +108,31 @@ pub fn setupDynamicAssets(
         },
         OutlineVolume {
             colour: configQuery.single().selection.selectionColour,
-            width: 3.,
+            width: SelectionOutlineWidth,
             visible: false,
         },
         RayCastPickable,
         // GizmoTarget::default(),
     ));
 }
+
+#[cfg(target_arch = "wasm32")]
+pub fn setupAutosave(
+    mut commands: Commands,
+    runner: Res<RunnerWrapper>,
+    configQuery: Query<&EditorConfiguration>,
+) {
+    commands.insert_resource(AutosaveState::new(&configQuery.single().autosave));
+
+    // Offer restoring the newest snapshot, the frontend answers with either the "restoreSnapshot" or "discardSnapshots" event
+    if let Ok(snapshots) = runner.recovery.snapshots.read() {
+        if let Some(snapshot) = newestSnapshot(&snapshots) {
+            triggerInterfaceCallbacks("recovery", vec![asJsObject(vec![
+                ("slot", (snapshot.slot as u32).into()),
+                ("timestamp", snapshot.timestamp.into()),
+                ("reason", snapshot.reason.as_str().into()),
+                ("entityCount", (snapshot.scene.entities.len() as u32).into()),
+            ])]);
+        }
+    }
+}
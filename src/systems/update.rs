@@ -1,5 +1,5 @@
 use bevy::{
-    ecs::system::SystemState, input::mouse::MouseMotion, picking::pointer::PointerInteraction, prelude::*, window::{
+    ecs::{component::ComponentInfo, system::SystemState}, input::mouse::MouseMotion, reflect::TypeRegistry, picking::{events::{Out, Over, Pointer}, pointer::PointerInteraction}, prelude::*, render::primitives::Aabb, window::{
         CursorGrabMode, 
         PrimaryWindow, 
     }
@@ -10,11 +10,11 @@ use bevy_mod_outline::{
 };
 use js_sys::{Object, Reflect, JsString};
 use std::collections::HashMap;
-use transform_gizmo_bevy::{prelude::*, GizmoTransform};
+use transform_gizmo_bevy::prelude::*;
 
 #[allow(unused_imports)]
 use crate::{
-    lib::editorvisibility::EditorVisible, wasm::definitions::consoleLog, EditorConfiguration, RotationCamera
+    lib::{duplication::*, editorvisibility::{editorResourceTypes, EditorVisible}, hierarchy::*, inspector::*, jscasting::{entityIntoJs, serdeAsJsObject}, layers::*, marquee::*, materials::*, metadata::typeFieldMetadata, naming::*, pivot::*, registration::EditorComponents, selection::*}, wasm::definitions::consoleLog, EditorConfiguration, EditorCursor, EditorHidden, EditorId, EditorLocked, RotationCamera
 };
 #[cfg(target_arch = "wasm32")]
 use {
@@ -22,42 +22,287 @@ use {
         wasm::data::*,
         lib::{
             assetloader::*,
+            autosave::*,
             history::*,
+            jscasting::asJsObject,
+            properties::{insertComponent, removeComponent, writeProperty, writeResourceProperty},
+            scene::*,
         },
+        systems::bridge::replaceScene,
         triggerInterfaceCallbacks,
         // consoleLog,
     },
     std::sync::atomic::Ordering,
+    transform_gizmo_bevy::GizmoTransform,
 };
 
+// Sends selection changes through the "selection" callback, whether they came from the viewport or the frontend, along with the inspector data of the new selection
+#[cfg(target_arch = "wasm32")]
 pub fn worldFrame(
     world: &mut World,
 ) {
-    let mut mouseButtonInputState: SystemState<Res<ButtonInput<MouseButton>>> = SystemState::new(world);
-    let mouseButtonInput = mouseButtonInputState.get(world);
+    if !updateSelection(world) { return; }
 
-    // Need to debug here, as it seems this might be triggering more than once per mouse press
-    if mouseButtonInput.just_pressed(MouseButton::Left) {
-        let mut gizmoTargetState: SystemState<Query<Entity, With<GizmoTarget>>> = SystemState::new(world);
-        let gizmoTarget = match gizmoTargetState.get(world).get_single() {
-            Ok(target) => target,
-            Err(_) => return triggerInterfaceCallbacks("properties", vec![]),
-        };
+    let selection = world.resource::<Selection>();
+    let entities = selection.entities.clone();
+    let active = selection.active();
 
-        let mut infoVec: Vec<Object> = vec![];
-        for (_, component) in world.inspect_entity(gizmoTarget).enumerate() {
-            if !component.isEditorVisible() { continue; }
+    triggerInterfaceCallbacks("selection", vec![serdeAsJsObject(&serde_json::json!({
+        "entities": entities.iter().map(|entity| entity.to_bits()).collect::<Vec<u64>>(),
+        "active": active.map(|entity| entity.to_bits()),
+    }))]);
+    sendSharedProperties(world, &entities);
+}
 
-            let obj = Object::new();
+// The native build has no frontend to tell about the selection, but the active entity is still needed as a pivot
+#[cfg(not(target_arch = "wasm32"))]
+pub fn trackSelection(
+    world: &mut World,
+) {
+    updateSelection(world);
+}
 
-            Reflect::set(&obj, &JsString::from("name"), &JsString::from(component.name())).unwrap();
-            Reflect::set(&obj, &JsString::from("info"), &component.getInfo(world, gizmoTarget).into()).unwrap();
+// Inspector data of a single component, as sent in the "properties" callback
+fn componentObject(
+    world: &World,
+    entity: Entity,
+    component: &ComponentInfo,
+    registry: &TypeRegistry,
+) -> Object {
+    let obj = Object::new();
 
-            infoVec.push(obj);
+    Reflect::set(&obj, &JsString::from("entity"), &entityIntoJs(entity)).unwrap();
+    Reflect::set(&obj, &JsString::from("name"), &JsString::from(component.name())).unwrap();
+    Reflect::set(&obj, &JsString::from("info"), &component.getInfo(world, entity).into()).unwrap();
+
+    // The material handle alone isn't much use, so the material itself is sent along
+    if component.type_id() == Some(std::any::TypeId::of::<MeshMaterial3d<StandardMaterial>>()) {
+        if let Some(material) = materialInfo(world, entity) {
+            Reflect::set(&obj, &JsString::from("material"), &serdeAsJsObject(&material).into()).unwrap();
+        }
+    }
+
+    let editorInfo = component.type_id().and_then(|typeId| world.get_resource::<EditorComponents>()?.get(typeId));
+    if let Some(typeInfo) = component.type_id().and_then(|typeId| registry.get_type_info(typeId)) {
+        Reflect::set(&obj, &JsString::from("metadata"), &serdeAsJsObject(&typeFieldMetadata(typeInfo, editorInfo)).into()).unwrap();
+    }
+
+    // Extra presentation info for components the game registered through `EditorAppExt`
+    if let Some(editorInfo) = editorInfo {
+        for (key, value) in [("displayName", &editorInfo.displayName), ("category", &editorInfo.category), ("icon", &editorInfo.icon)] {
+            if let Some(value) = value {
+                Reflect::set(&obj, &JsString::from(key), &JsString::from(value.as_str())).unwrap();
+            }
+        }
+    }
+
+    return obj;
+}
+
+// The name is shown at the top of the inspector
+fn namedFirst<'a>(components: impl Iterator<Item = &'a ComponentInfo>) -> Vec<&'a ComponentInfo> {
+    let mut components = components.collect::<Vec<&ComponentInfo>>();
+    components.sort_by_key(|component| component.type_id() != Some(std::any::TypeId::of::<Name>()));
+    components
+}
+
+// Sends the inspector data of every editable component on an entity through the "properties" callback
+#[cfg(target_arch = "wasm32")]
+pub fn sendProperties(
+    world: &World,
+    entity: Entity,
+) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut infoVec: Vec<Object> = vec![];
+    for component in namedFirst(world.inspect_entity(entity)) {
+        if !component.isEditorVisible(&registry) { continue; }
+
+        infoVec.push(componentObject(world, entity, component, &registry));
+    }
+
+    triggerInterfaceCallbacks("properties", infoVec);
+}
+
+// Sends the resources shown as scene settings through the "resources" callback, shaped like the "properties" of a component
+#[cfg(target_arch = "wasm32")]
+pub fn sendResources(
+    world: &World,
+) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut infoVec: Vec<Object> = vec![];
+    for typeId in editorResourceTypes() {
+        let Some(registration) = registry.get(*typeId) else { continue; };
+        let Some(resource) = registration.data::<ReflectResource>().and_then(|reflectResource| reflectResource.reflect(world)) else { continue; };
+
+        let obj = Object::new();
+        Reflect::set(&obj, &JsString::from("name"), &JsString::from(registration.type_info().type_path())).unwrap();
+        Reflect::set(&obj, &JsString::from("info"), &serdeAsJsObject(&inspectValue(resource.as_partial_reflect(), &registry)).into()).unwrap();
+        Reflect::set(&obj, &JsString::from("metadata"), &serdeAsJsObject(&typeFieldMetadata(registration.type_info(), None)).into()).unwrap();
+
+        infoVec.push(obj);
+    }
+
+    triggerInterfaceCallbacks("resources", infoVec);
+}
+
+// Same as `sendProperties` for several entities, only components all of them have are sent.
+// Values are those of the first entity, with the paths of fields that differ between the entities listed in "mixed"
+#[cfg(target_arch = "wasm32")]
+pub fn sendSharedProperties(
+    world: &World,
+    entities: &[Entity],
+) {
+    let Some((&first, others)) = entities.split_first() else { return triggerInterfaceCallbacks("properties", vec![]); };
+    if others.is_empty() { return sendProperties(world, first); }
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let entityArray = js_sys::Array::new();
+    for entity in entities {
+        entityArray.push(&entityIntoJs(*entity));
+    }
+
+    let mut infoVec: Vec<Object> = vec![];
+    for component in namedFirst(world.inspect_entity(first)) {
+        if !component.isEditorVisible(&registry) { continue; }
+        let Some(typeId) = component.type_id() else { continue; };
+        if !others.iter().all(|other| world.get_entity(*other).is_ok_and(|other| other.contains_type_id(typeId))) { continue; }
+
+        let mut mixed: Vec<serde_json::Value> = vec![];
+        if let Some(info) = componentValue(world, first, typeId) {
+            for other in others {
+                let Some(otherInfo) = componentValue(world, *other, typeId) else { continue; };
+
+                let mut changes = vec![];
+                diffValues(&info, &otherInfo, &mut vec![], &mut changes);
+                for change in changes {
+                    if !mixed.contains(&change["path"]) {
+                        mixed.push(change["path"].clone());
+                    }
+                }
+            }
+        }
+
+        let obj = componentObject(world, first, component, &registry);
+        Reflect::set(&obj, &JsString::from("entities"), &entityArray).unwrap();
+        Reflect::set(&obj, &JsString::from("mixed"), &serdeAsJsObject(&mixed).into()).unwrap();
+
+        infoVec.push(obj);
+    }
+
+    triggerInterfaceCallbacks("properties", infoVec);
+}
+
+// Pushes changes to watched entities to the inspector, at most once per frame, through the "propertyChanges" callback
+#[cfg(target_arch = "wasm32")]
+pub fn pushInspectorUpdates(
+    world: &mut World,
+) {
+    let thisRun = world.read_change_tick();
+
+    world.resource_scope(|world, mut watches: Mut<InspectorWatches>| {
+        let lastRun = watches.lastCheck;
+        watches.lastCheck = thisRun;
+        if watches.entities.is_empty() { return; }
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+
+        let mut updates: Vec<Object> = vec![];
+        watches.entities.retain(|&entity, watched| {
+            let Ok(entityRef) = world.get_entity(entity) else {
+                updates.push(serdeAsJsObject(&serde_json::json!({ "entity": entity.to_bits(), "despawned": true })));
+                return false;
+            };
+
+            let components = watchedComponents(world, entity, &watched.components, &registry);
+
+            // Components were added or removed, so the whole inspector is sent again instead
+            if components.len() != watched.sent.len() || components.iter().any(|(typeId, _)| !watched.sent.contains_key(typeId)) {
+                watched.sent = watchBaseline(world, entity, &watched.components, &registry);
+                sendProperties(world, entity);
+                return true;
+            }
+
+            for (typeId, name) in components {
+                let isChanged = world.components().get_id(typeId)
+                    .and_then(|componentId| entityRef.get_change_ticks_by_id(componentId))
+                    .is_some_and(|ticks| ticks.is_changed(lastRun, thisRun));
+                if !isChanged { continue; }
+
+                let Some(value) = componentValue(world, entity, typeId) else { continue; };
+                let mut changes = vec![];
+                if let Some(previous) = watched.sent.get(&typeId) {
+                    diffValues(previous, &value, &mut vec![], &mut changes);
+                }
+
+                // Mutable access marks a component as changed even if nothing was written
+                if changes.is_empty() { continue; }
+
+                updates.push(serdeAsJsObject(&serde_json::json!({ "entity": entity.to_bits(), "name": name, "changes": changes })));
+                watched.sent.insert(typeId, value);
+            }
+
+            true
+        });
+
+        if !updates.is_empty() {
+            triggerInterfaceCallbacks("propertyChanges", updates);
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn autosave(
+    world: &mut World,
+) {
+    let runner = world.resource::<RunnerWrapper>();
+    let history = runner.history.clone();
+    let recovery = runner.recovery.clone();
+
+    if recovery.restoreRequested.swap(false, Ordering::SeqCst) {
+        let snapshot = recovery.snapshots.read().ok().and_then(|snapshots| newestSnapshot(&snapshots).cloned());
+
+        if let Some(snapshot) = snapshot {
+            if let Err(e) = replaceScene(world, &snapshot.scene) {
+                consoleLog(&format!("Failed to fully restore the snapshot:\n{}", e));
+            }
+        }
+        if let Ok(mut snapshots) = recovery.snapshots.write() {
+            snapshots.clear();
         }
-        
-        triggerInterfaceCallbacks("properties", infoVec);
     }
+
+    let delta = world.resource::<Time>().delta();
+    let historyLength = history.read().map_or(0, |history| history.past.len());
+
+    world.resource_scope(|world, mut state: Mut<AutosaveState>| {
+        let mut configQuery = world.query::<&EditorConfiguration>();
+        let Ok(config) = configQuery.get_single(world) else { return; };
+        let Some(reason) = state.tick(delta, historyLength, &config.autosave) else { return; };
+
+        let snapshot = RecoverySnapshot {
+            slot: state.takeSlot(&config.autosave),
+            timestamp: js_sys::Date::now(),
+            reason: reason.to_string(),
+            scene: captureScene(world),
+        };
+
+        match serde_json::to_string(&snapshot) {
+            Ok(json) => triggerInterfaceCallbacks("autosave", vec![asJsObject(vec![
+                ("slot", (snapshot.slot as u32).into()),
+                ("timestamp", snapshot.timestamp.into()),
+                ("reason", reason.into()),
+                ("snapshot", json.into()),
+            ])]),
+            Err(e) => consoleLog(&format!("Failed to serialize autosave snapshot: {}", e)),
+        }
+    });
 }
 
 pub fn mouseInteractions(
@@ -65,9 +310,14 @@ pub fn mouseInteractions(
     mut query: Query<&mut Transform, With<RotationCamera>>,
     mut mouseMotionEvents: EventReader<MouseMotion>,
     mut clickables: Query<(Entity, &mut OutlineVolume), (With<Mesh3d>, With<OutlineStencil>)>,
+    unpickables: Query<(), Or<(With<EditorLocked>, With<EditorHidden>)>>,
+    entityLayers: Query<&OnEditorLayer>,
+    layers: Res<EditorLayers>,
+    marquee: Res<Marquee>,
     mut commands: Commands,
     gizmoTargets: Query<Entity, With<GizmoTarget>>,
     mouseButtonInput: Res<ButtonInput<MouseButton>>,
+    keyboardInput: Res<ButtonInput<KeyCode>>,
     pointers: Query<&PointerInteraction>,
 ) {
     let mut window = windows.single_mut();
@@ -88,20 +338,38 @@ pub fn mouseInteractions(
             transform.rotation = yaw * transform.rotation * pitch;
         }
     }
-    if mouseButtonInput.just_pressed(MouseButton::Left) {
-        // Remove GizmoTargets in existing places and make selection outlines invisible
-        for entity in gizmoTargets.iter() {
-            commands.entity(entity).remove::<GizmoTarget>();
-        }
-        for mut clickable in clickables.iter_mut() {
-            clickable.1.visible = false;
+    // Clicks select on release, so a press that turns into a marquee drag (or started on the gizmo) doesn't select anything
+    if mouseButtonInput.just_released(MouseButton::Left) && marquee.start.is_some() && !marquee.active {
+        // Shift adds to the selection and Control toggles the clicked entity, a plain click replaces the selection
+        let additive = keyboardInput.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let toggle = keyboardInput.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+
+        // Hits are sorted nearest first, locked and hidden entities and ones on unselectable layers are clicked through
+        let hit = pointers.iter().flat_map(|interaction| interaction.iter()).find(|(entity, _)| {
+            clickables.contains(*entity) && !unpickables.contains(*entity) && isOnSelectableLayer(&layers, entityLayers.get(*entity).ok())
+        }).map(|(entity, _)| *entity);
+
+        if !additive && !toggle {
+            // Remove GizmoTargets in existing places and make selection outlines invisible
+            for entity in gizmoTargets.iter() {
+                commands.entity(entity).remove::<GizmoTarget>();
+            }
+            for mut clickable in clickables.iter_mut() {
+                clickable.1.visible = false;
+            }
         }
 
-        if let Some((point, _normal)) = pointers.iter().filter_map(|interaction| interaction.get_nearest_hit()).into_iter().nth(0) {
-            if let Ok((entity, mut outlineVolume)) = clickables.get_mut(*point) {
+        if let Some(entity) = hit {
+            let deselect = toggle && gizmoTargets.contains(entity);
+
+            if let Ok((_, mut outlineVolume)) = clickables.get_mut(entity) {
                 // Handle outline and gizmos
-                outlineVolume.visible = true;
-                commands.entity(entity).insert(GizmoTarget::default());
+                outlineVolume.visible = !deselect;
+                if deselect {
+                    commands.entity(entity).remove::<GizmoTarget>();
+                } else if !gizmoTargets.contains(entity) || (!additive && !toggle) {
+                    commands.entity(entity).insert(GizmoTarget::default());
+                }
             }
         }
     }
@@ -120,11 +388,245 @@ pub fn mouseInteractions(
     }
 }
 
+// Left-dragging in the viewport selects everything whose screen bounds touch the rectangle.
+// Shift adds to the selection, Control removes from it, otherwise the selection is replaced
+pub fn marqueeSelection(
+    mut marquee: ResMut<Marquee>,
+    mut overlays: Query<(&mut Node, &mut Visibility), With<MarqueeOverlay>>,
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<RotationCamera>>,
+    gizmoTargets: Query<&GizmoTarget>,
+    candidates: Query<(Entity, &Aabb, &GlobalTransform, &ViewVisibility, Option<&OnEditorLayer>), (With<Mesh3d>, With<OutlineStencil>, With<EditorId>, Without<EditorLocked>, Without<EditorHidden>)>,
+    layers: Res<EditorLayers>,
+    mouseButtonInput: Res<ButtonInput<MouseButton>>,
+    keyboardInput: Res<ButtonInput<KeyCode>>,
+) {
+    let Some(cursor) = windows.get_single().ok().and_then(|window| window.cursor_position()) else { return; };
+
+    // Dragging a gizmo handle moves the selection instead
+    if mouseButtonInput.just_pressed(MouseButton::Left) {
+        *marquee = if gizmoTargets.iter().any(|target| target.is_focused()) {
+            Marquee::default()
+        } else {
+            Marquee { start: Some(cursor), current: cursor, active: false }
+        };
+    }
+
+    if let (true, Some(start)) = (mouseButtonInput.pressed(MouseButton::Left), marquee.start) {
+        marquee.current = cursor;
+        marquee.active |= start.distance(cursor) > MarqueeThreshold;
+    }
+
+    if mouseButtonInput.just_released(MouseButton::Left) {
+        if let (Some(rect), Ok((camera, cameraTransform))) = (marquee.rect(), cameras.get_single()) {
+            // Children of hidden entities don't carry `EditorHidden` themselves, but aren't visible either
+            let found = candidates.iter()
+                .filter(|(_, _, _, viewVisibility, layer)| viewVisibility.get() && isOnSelectableLayer(&layers, *layer))
+                .filter(|(_, aabb, transform, _, _)| {
+                    screenBounds(camera, cameraTransform, aabb, transform).is_some_and(|bounds| !rect.intersect(bounds).is_empty())
+                })
+                .map(|(entity, _, _, _, _)| entity)
+                .collect::<Vec<Entity>>();
+
+            let mode = if keyboardInput.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+                SelectionMode::Add
+            } else if keyboardInput.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+                SelectionMode::Remove
+            } else {
+                SelectionMode::Replace
+            };
+            // Removing nothing would clear the selection
+            if mode != SelectionMode::Remove || !found.is_empty() {
+                commands.queue(move |world: &mut World| {
+                    if let Err(e) = selectEntities(world, &found, mode) {
+                        warn!("Failed to select marquee: {}", e);
+                    }
+                });
+            }
+        }
+
+        *marquee = Marquee::default();
+    }
+
+    for (mut node, mut visibility) in overlays.iter_mut() {
+        match marquee.rect() {
+            Some(rect) => {
+                node.left = Val::Px(rect.min.x);
+                node.top = Val::Px(rect.min.y);
+                node.width = Val::Px(rect.width());
+                node.height = Val::Px(rect.height());
+                *visibility = Visibility::Visible;
+            },
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+// Outlines the hovered entity thinner and in the highlight colour, selected entities keep their selection outline.
+// Changes of the hovered entity are sent through the "hover" callback, e.g. for a status bar
+pub fn hoverHighlight(
+    mut hovered: ResMut<HoveredEntity>,
+    mut overEvents: EventReader<Pointer<Over>>,
+    mut outEvents: EventReader<Pointer<Out>>,
+    mut outlines: Query<(&mut OutlineVolume, Has<GizmoTarget>)>,
+    pickables: Query<Option<&OnEditorLayer>, (With<Mesh3d>, With<OutlineStencil>, Without<EditorLocked>, Without<EditorHidden>)>,
+    names: Query<&Name>,
+    layers: Res<EditorLayers>,
+    configQuery: Query<&EditorConfiguration>,
+) {
+    let previous = hovered.0;
+    let isPickable = |entity: Entity| pickables.get(entity).is_ok_and(|layer| isOnSelectableLayer(&layers, layer));
+
+    for event in outEvents.read() {
+        if hovered.0 == Some(event.target) { hovered.0 = None; }
+    }
+    for event in overEvents.read() {
+        if isPickable(event.target) { hovered.0 = Some(event.target); }
+    }
+    // Entities that got locked or hidden while hovered stop being highlighted
+    if hovered.0.is_some_and(|entity| !isPickable(entity)) {
+        hovered.0 = None;
+    }
+
+    let selection = &configQuery.single().selection;
+    let mut applyOutline = |entity: Entity, isHovered: bool| {
+        let Ok((mut outline, isSelected)) = outlines.get_mut(entity) else { return; };
+        let (visible, colour, width) = match (isSelected, isHovered) {
+            (true, _) => (true, selection.selectionColour, SelectionOutlineWidth),
+            (false, true) => (true, selection.highlightColour, HoverOutlineWidth),
+            (false, false) => (false, selection.selectionColour, SelectionOutlineWidth),
+        };
+
+        // Only written when something changed, so the outline isn't marked as changed every frame
+        if outline.visible != visible || outline.colour != colour || outline.width != width {
+            outline.visible = visible;
+            outline.colour = colour;
+            outline.width = width;
+        }
+    };
+
+    // The hovered entity is refreshed every frame, as clicking it changes whether it's selected
+    if let Some(entity) = hovered.0 {
+        applyOutline(entity, true);
+    }
+    if previous == hovered.0 { return; }
+    if let Some(entity) = previous {
+        applyOutline(entity, false);
+    }
+
+    #[allow(unused_variables)]
+    let name = hovered.0.and_then(|entity| names.get(entity).ok()).map(|name| name.as_str());
+
+    // The native build has no frontend to tell
+    #[cfg(target_arch = "wasm32")]
+    triggerInterfaceCallbacks("hover", vec![serdeAsJsObject(&serde_json::json!({
+        "entity": hovered.0.map(|entity| entity.to_bits()),
+        "name": name,
+    }))]);
+}
+
+// The gizmo only knows about median points and individual origins, for the other pivot modes the selection is recomputed every frame of a drag,
+// from where it was before the drag and around the chosen pivot
+pub fn applyPivot(
+    settings: Res<PivotSettings>,
+    selection: Res<Selection>,
+    mut drag: ResMut<PivotDrag>,
+    mut targets: Query<(Entity, &GizmoTarget, &mut Transform, &GlobalTransform, Option<&Parent>, Option<&Aabb>)>,
+    globals: Query<&GlobalTransform>,
+    cursors: Query<&GlobalTransform, With<EditorCursor>>,
+) {
+    let active = targets.iter().any(|(_, target, ..)| target.is_active());
+
+    if active && !drag.dragging {
+        let pivotTargets = drag.start.iter().map(|(entity, transform, _)| PivotTarget {
+            entity: *entity,
+            transform: *transform,
+            bounds: targets.get(*entity).ok().and_then(|(_, _, _, global, _, aabb)| aabb.map(|aabb| worldBounds(aabb, global))),
+        }).collect::<Vec<PivotTarget>>();
+
+        drag.pivot = pivotPoint(settings.mode, &pivotTargets, selection.active(), cursors.get_single().ok().map(|cursor| cursor.translation()));
+        drag.dragging = true;
+    }
+
+    // The gizmo moves its targets by another step of its own after this runs, so the transforms are set from scratch every frame,
+    // and once more with the last result after the drag ended, which overrides whatever the gizmo did on its final frame
+    let result = if active {
+        drag.result = targets.iter().find_map(|(_, target, ..)| target.latest_result());
+        drag.result
+    } else {
+        drag.result.take()
+    };
+
+    // Moving doesn't depend on the pivot
+    if let Some(result) = result.filter(|result| !settings.mode.isNative() && !matches!(result, GizmoResult::Translation { .. })) {
+        for (entity, start, parentGlobal) in drag.start.iter() {
+            let Ok((_, _, mut transform, ..)) = targets.get_mut(*entity) else { continue; };
+            let world = pivotTransform(*start, &result, drag.pivot);
+
+            *transform = match parentGlobal {
+                Some(parentGlobal) => GlobalTransform::from(world).reparented_to(parentGlobal),
+                None => world,
+            };
+        }
+    }
+
+    if !active {
+        // Taken every frame until a drag starts, so the drag starts from before the gizmo changed anything
+        drag.dragging = false;
+        drag.start = targets.iter()
+            .map(|(entity, _, _, global, parent, _)| (entity, global.compute_transform(), parent.and_then(|parent| globals.get(parent.get()).ok()).copied()))
+            .collect();
+    }
+}
+
+// Draws the 3D cursor, Shift+C places it on the surface under the mouse, or back at the origin if there's nothing there
+pub fn editorCursor(
+    mut cursors: Query<&mut Transform, With<EditorCursor>>,
+    mut gizmos: Gizmos,
+    keyboardInput: Res<ButtonInput<KeyCode>>,
+    pointers: Query<&PointerInteraction>,
+) {
+    let Ok(mut cursor) = cursors.get_single_mut() else { return; };
+
+    if keyboardInput.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) && keyboardInput.just_pressed(KeyCode::KeyC) {
+        let hit = pointers.iter().find_map(|interaction| interaction.get_nearest_hit()).and_then(|(_, hit)| hit.position);
+        cursor.translation = hit.unwrap_or(Vec3::ZERO);
+    }
+
+    let colour = Color::srgb(1., 0.3, 0.3);
+    gizmos.sphere(Isometry3d::from_translation(cursor.translation), 0.15, colour);
+    gizmos.cross(Isometry3d::from_translation(cursor.translation), 0.3, Color::WHITE);
+}
+
+// Keeps the gizmo's own pivot in line with the pivot mode, and tells the frontend about pivot changes through the "pivot" callback
+pub fn syncPivot(
+    settings: Res<PivotSettings>,
+    mut gizmoOptions: ResMut<GizmoOptions>,
+    cursors: Query<Ref<Transform>, With<EditorCursor>>,
+) {
+    let cursor = cursors.get_single().ok();
+    if !settings.is_changed() && !cursor.as_ref().is_some_and(|cursor| cursor.is_changed()) { return; }
+
+    if settings.is_changed() {
+        gizmoOptions.pivot_point = if settings.mode == PivotMode::IndividualOrigins { TransformPivotPoint::IndividualOrigins } else { TransformPivotPoint::MedianPoint };
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    triggerInterfaceCallbacks("pivot", vec![serdeAsJsObject(&serde_json::json!({
+        "mode": settings.mode,
+        "cursor": cursor.map(|cursor| cursor.translation.to_array()),
+    }))]);
+}
+
 pub fn keyboardInteractions(
     mut cameraTransformQuery: Query<&mut Transform, With<RotationCamera>>,
     mut gizmoSettings: ResMut<GizmoOptions>,
+    mut commands: Commands,
     keyboardInput: Res<ButtonInput<KeyCode>>,
     configQuery: Query<&EditorConfiguration>,
+    gizmoTargets: Query<Entity, With<GizmoTarget>>,
+    mut pivotSettings: ResMut<PivotSettings>,
     time: Res<Time>,
 ) {
     let cameraSpeed = configQuery.single().camera.cameraSpeed;
@@ -144,15 +646,39 @@ pub fn keyboardInteractions(
     ].into_iter().collect();
 
     for (key, vec) in directionKeyMap.iter() {
-        if !keyboardInput.pressed(*key) { continue }
+        // Holding control means a shortcut is being used (e.g. Ctrl+D), so the camera shouldn't move along
+        if !keyboardInput.pressed(*key) || keyboardInput.pressed(KeyCode::ControlLeft) { continue }
 
         let forward = cameraTransform.rotation * *vec;
         cameraTransform.translation += forward * cameraSpeed * time.delta_secs();
     }
 
+    // Cycles through the pivot modes, like Blender's pivot menu on the period key
+    if keyboardInput.just_pressed(KeyCode::Period) {
+        pivotSettings.mode = pivotSettings.mode.next();
+    }
+
     if keyboardInput.pressed(KeyCode::ControlLeft) && keyboardInput.just_pressed(KeyCode::KeyL) {
         gizmoSettings.gizmo_orientation = if gizmoSettings.gizmo_orientation == GizmoOrientation::Global { GizmoOrientation::Local } else { GizmoOrientation::Global }
     }
+
+    if keyboardInput.pressed(KeyCode::ControlLeft) && keyboardInput.just_pressed(KeyCode::KeyD) {
+        let sources = gizmoTargets.iter().collect::<Vec<Entity>>();
+        if sources.is_empty() { return; }
+
+        commands.queue(move |world: &mut World| {
+            #[allow(unused_variables)]
+            let copies = duplicateSelection(world, &sources);
+
+            #[cfg(target_arch = "wasm32")]
+            if let Some(runner) = world.get_resource::<RunnerWrapper>() {
+                if let Ok(mut history) = runner.history.write() {
+                    history.future.clear();
+                    history.past.push(HistoryItem::Duplicate { sources, copies });
+                }
+            }
+        });
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -161,10 +687,12 @@ pub fn syncData(
     mut commands: Commands,
     meshes: ResMut<Assets<Mesh>>,
     images: ResMut<Assets<Image>>,
+    mut loadedImages: ResMut<LoadedImages>,
     last: Res<PreviousCustomGizmoOptions>,
     sync: Res<CustomGizmoOptions>,
     runner: Res<RunnerWrapper>,
     materials: Query<&MeshMaterial3d<StandardMaterial>>,
+    names: Query<&Name>,
     configQuery: Query<&EditorConfiguration>,
 ) {
     // Handling Gizmo option flags
@@ -190,11 +718,15 @@ pub fn syncData(
     if let Ok(mut modelGuard) = runner.binaryData.model.write() {
         if let Some(model) = modelGuard.take() {
             let importedMeshes = loadModel(meshes, &model).unwrap();
+            let mut taken = names.iter().map(|name| name.as_str().to_string()).collect::<bevy::utils::HashSet<String>>();
 
-            for meshHandle in importedMeshes.iter() {
+            for (name, meshHandle) in importedMeshes.iter() {
                 let material = materials.single();
+                let name = uniqueName(&taken, name);
+                taken.insert(name.clone());
 
                 commands.spawn((
+                    Name::new(name),
                     Mesh3d(meshHandle.clone()),
                     material.clone(),
                     OutlineStencil {
@@ -203,7 +735,7 @@ pub fn syncData(
                     },
                     OutlineVolume {
                         colour: configQuery.single().selection.selectionColour,
-                        width: 3.,
+                        width: SelectionOutlineWidth,
                         visible: false,
                     },
                     RayCastPickable,
@@ -214,41 +746,273 @@ pub fn syncData(
     }
     if let Ok(mut imageGuard) = runner.binaryData.image.write() {
         if let Some(image) = imageGuard.take() {
-            loadImage(images, &image).unwrap();
+            let handle = loadImage(images, &image).unwrap();
+
+            triggerInterfaceCallbacks("imageLoaded", vec![asJsObject(vec![("id", handle.id().to_string().into())])]);
+            loadedImages.0.push(handle);
+        }
+    }
+}
+
+// Sends outliner updates through the "hierarchy" callback, as added/removed/renamed/reparented/flags/layer entries following the snapshot
+#[cfg(target_arch = "wasm32")]
+pub fn streamHierarchy(
+    stream: Res<HierarchyStream>,
+    entities: Query<EntityRef, With<EditorId>>,
+    added: Query<Entity, Added<EditorId>>,
+    renamed: Query<(Entity, &Name), (Changed<Name>, With<EditorId>)>,
+    reparented: Query<(Entity, &Parent), (Changed<Parent>, With<EditorId>)>,
+    flagged: Query<Entity, (Or<(Added<EditorHidden>, Added<EditorLocked>)>, With<EditorId>)>,
+    relayered: Query<(Entity, &OnEditorLayer), (Changed<OnEditorLayer>, With<EditorId>)>,
+    mut removedIds: RemovedComponents<EditorId>,
+    mut removedParents: RemovedComponents<Parent>,
+    mut removedHidden: RemovedComponents<EditorHidden>,
+    mut removedLocked: RemovedComponents<EditorLocked>,
+    mut removedLayers: RemovedComponents<OnEditorLayer>,
+) {
+    // Reading the removals regardless, so they don't pile up until the stream gets enabled
+    let removedIds = removedIds.read().collect::<Vec<Entity>>();
+    let removedParents = removedParents.read().collect::<Vec<Entity>>();
+    let mut unflagged = removedHidden.read().chain(removedLocked.read()).collect::<Vec<Entity>>();
+    let removedLayers = removedLayers.read().collect::<Vec<Entity>>();
+    if !stream.enabled { return; }
+
+    let mut updates: Vec<Object> = vec![];
+    for entity in added.iter() {
+        if let Ok(entityRef) = entities.get(entity) {
+            updates.push(serdeAsJsObject(&serde_json::json!({ "type": "added", "node": hierarchyNode(entityRef) })));
         }
     }
+
+    // Added entities are sent whole, so only changes to existing ones are sent separately
+    for (entity, name) in renamed.iter() {
+        if added.contains(entity) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "renamed", "entity": entity.to_bits(), "name": name.as_str() })));
+    }
+    for (entity, parent) in reparented.iter() {
+        if added.contains(entity) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "reparented", "entity": entity.to_bits(), "parent": parent.get().to_bits() })));
+    }
+    // Entities that lost their parent without being despawned were moved to the root
+    for entity in removedParents {
+        if !entities.contains(entity) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "reparented", "entity": entity.to_bits(), "parent": null })));
+    }
+    unflagged.extend(flagged.iter());
+    unflagged.sort();
+    unflagged.dedup();
+    for entity in unflagged {
+        if added.contains(entity) { continue; }
+        let Ok(entityRef) = entities.get(entity) else { continue; };
+        updates.push(serdeAsJsObject(&serde_json::json!({
+            "type": "flags",
+            "entity": entity.to_bits(),
+            "hidden": entityRef.contains::<EditorHidden>(),
+            "locked": entityRef.contains::<EditorLocked>(),
+        })));
+    }
+    for (entity, layer) in relayered.iter() {
+        if added.contains(entity) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "layer", "entity": entity.to_bits(), "layer": layer.0 })));
+    }
+    for entity in removedLayers {
+        if !entities.get(entity).is_ok_and(|entityRef| !entityRef.contains::<OnEditorLayer>()) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "layer", "entity": entity.to_bits(), "layer": null })));
+    }
+    for entity in removedIds {
+        if entities.contains(entity) { continue; }
+        updates.push(serdeAsJsObject(&serde_json::json!({ "type": "removed", "entity": entity.to_bits() })));
+    }
+
+    if !updates.is_empty() {
+        triggerInterfaceCallbacks("hierarchy", updates);
+    }
 }
 
+// Names new scene entities after what they are, e.g. "Mesh.002"
+pub fn assignDefaultNames(
+    world: &mut World,
+) {
+    let mut unnamedState: SystemState<Query<Entity, (With<Transform>, Without<Name>, Without<RotationCamera>, Without<EditorCursor>, Without<Node>)>> = SystemState::new(world);
+    let unnamed = unnamedState.get(world).iter().collect::<Vec<Entity>>();
+    if unnamed.is_empty() { return; }
+
+    let mut taken = takenNames(world);
+    for entity in unnamed {
+        let name = uniqueName(&taken, defaultName(world.entity(entity)));
+        taken.insert(name.clone());
+        world.entity_mut(entity).insert(Name::new(name));
+    }
+}
+
+// Gives every scene entity (see `isSceneEntity`) an id which is kept when saving and loading
+pub fn assignEditorIds(
+    mut commands: Commands,
+    unassigned: Query<Entity, (With<Transform>, Without<EditorId>, Without<RotationCamera>, Without<EditorCursor>, Without<Node>)>,
+) {
+    for entity in unassigned.iter() {
+        // Kept within 53 bits, so ids survive being passed through JS numbers
+        commands.entity(entity).insert(EditorId(rand::random::<u64>() >> 11));
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
 pub fn handleHistory(
     mut gizmoEvents: EventReader<GizmoTransform>,
     mut runnerWrapper: ResMut<RunnerWrapper>,
-    mut transformableEntityQuery: Query<(Entity, &mut Transform)>,
+    transformableEntityQuery: Query<(Entity, &Transform)>,
+    pivotSettings: Res<PivotSettings>,
+    pivotDrag: Res<PivotDrag>,
 ) {
-    let events = gizmoEvents.read().collect::<Vec<&GizmoTransform>>();
+    // A gizmo transforming several entities sends an event for each of them in the same frame
+    let events = gizmoEvents.read()
+        .filter(|gizmoTransform| transformableEntityQuery.contains(gizmoTransform.0))
+        .copied()
+        .collect::<Vec<GizmoTransform>>();
     let runner = runnerWrapper.as_mut();
 
-    if !events.is_empty() {
-        for gizmoTransform in events {
-            for (entity, transform) in transformableEntityQuery.iter_mut() {
-                if entity != gizmoTransform.0 { continue; }
-    
-                if let Ok(mut history) = runner.history.write() {
-                    history.future.clear();
-                    history.past.push(
-                        HistoryItem::Transform(
-                            *gizmoTransform,
-                            *transform,
-                        ),
-                    );
+    if events.is_empty() { return; }
+
+    // A single entity pivoting around its own origin is undone the same way it always was
+    let historyItem = if events.len() == 1 && pivotSettings.mode.isNative() {
+        let (_, transform) = transformableEntityQuery.get(events[0].0).unwrap();
+        HistoryItem::Transform(events[0], *transform)
+    } else {
+        HistoryItem::GroupTransform { targets: events, pivot: pivotDrag.pivot }
+    };
+
+    if let Ok(mut history) = runner.history.write() {
+        history.future.clear();
+        history.past.push(historyItem);
+    }
+}
+
+// Undoes or redoes a single history item, anything that needs world access is queued through `commands`
+#[cfg(target_arch = "wasm32")]
+fn applyHistoryItem(
+    historyItem: &HistoryItem,
+    isUndo: bool,
+    transformableEntityQuery: &mut Query<(Entity, &mut Transform)>,
+    commands: &mut Commands,
+) {
+    match historyItem.clone() {
+        HistoryItem::Transform(gizmoTransform, _) => {
+            let Ok((_, mut transform)) = transformableEntityQuery.get_mut(gizmoTransform.0) else { return; };
+
+            if isUndo {
+                match gizmoTransform.1 {
+                    GizmoResult::Translation { delta: _, total } => {
+                        transform.translation -= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+                    },
+                    GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
+                        transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), -total as f32);
+                    },
+                    GizmoResult::Scale { total } => {
+                        transform.scale /= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+                    },
+                    _ => {},
+                };
+            } else {
+                match gizmoTransform.1 {
+                    GizmoResult::Translation { delta: _, total } => {
+                        transform.translation += Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+                    },
+                    GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
+                        transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), total as f32);
+                    },
+                    GizmoResult::Scale { total } => {
+                        transform.scale *= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
+                    },
+                    _ => {},
+                };
+            }
+        },
+        HistoryItem::GroupTransform { targets, pivot } => {
+            // The pivot is in world space, so the parents' global transforms are needed
+            commands.queue(move |world: &mut World| {
+                for gizmoTransform in targets {
+                    let parentGlobal = world.get::<Parent>(gizmoTransform.0).and_then(|parent| world.get::<GlobalTransform>(parent.get())).copied();
+                    let Some(mut transform) = world.get_mut::<Transform>(gizmoTransform.0) else { continue; };
+                    applyGroupTransform(&mut transform, parentGlobal.as_ref(), &gizmoTransform.1, pivot, isUndo);
+                }
+            });
+        },
+        HistoryItem::Property { entity, componentName, property, before, after } => {
+            commands.queue(move |world: &mut World| {
+                if let Err(e) = writeProperty(world, entity, &componentName, &property, if isUndo { &before } else { &after }) {
+                    consoleLog(&format!("Failed to {} property edit: {}", if isUndo { "undo" } else { "redo" }, e));
+                }
+            });
+        },
+        HistoryItem::Component { entity, componentName, value, inserted } => {
+            commands.queue(move |world: &mut World| {
+                // Undoing an insertion and redoing a removal both remove the component
+                let result = if isUndo == inserted {
+                    removeComponent(world, entity, &componentName).map(|_| ())
+                } else {
+                    insertComponent(world, entity, &componentName, Some(&*value)).map(|_| ())
+                };
+
+                match result {
+                    Ok(_) => sendProperties(world, entity),
+                    Err(e) => consoleLog(&format!("Failed to {} component change: {}", if isUndo { "undo" } else { "redo" }, e)),
+                }
+            });
+        },
+        HistoryItem::Material { entity, material, previous, property, before, after } => {
+            commands.queue(move |world: &mut World| {
+                if let Err(e) = writeMaterialProperty(world, &material, &property, if isUndo { &before } else { &after }) {
+                    consoleLog(&format!("Failed to {} material edit: {}", if isUndo { "undo" } else { "redo" }, e));
+                }
+
+                // Forked materials are swapped back and forth along with the edit
+                if let (Some(previous), Ok(mut entity)) = (previous, world.get_entity_mut(entity)) {
+                    entity.insert(MeshMaterial3d(if isUndo { previous } else { material }));
+                }
+            });
+        },
+        HistoryItem::Resource { resourceName, property, before, after } => {
+            commands.queue(move |world: &mut World| {
+                match writeResourceProperty(world, &resourceName, &property, if isUndo { &before } else { &after }) {
+                    Ok(_) => sendResources(world),
+                    Err(e) => consoleLog(&format!("Failed to {} resource edit: {}", if isUndo { "undo" } else { "redo" }, e)),
+                }
+            });
+        },
+        HistoryItem::Reparent { entity, before, after } => {
+            commands.queue(move |world: &mut World| {
+                let (parent, transform) = if isUndo { before } else { after };
+                if let Err(e) = setParent(world, entity, parent, transform) {
+                    consoleLog(&format!("Failed to {} reparenting: {}", if isUndo { "undo" } else { "redo" }, e));
                 }
+            });
+        },
+        HistoryItem::Duplicate { sources, copies } => {
+            // Redoing creates new entities, which `handleUndoRedo` takes care of
+            if isUndo {
+                commands.queue(move |world: &mut World| removeDuplicates(world, &sources, &copies));
             }
-        }
-    }
+        },
+        HistoryItem::Batch(items) => {
+            // Undone back to front, so items touching the same value end up where they started
+            if isUndo {
+                for item in items.iter().rev() {
+                    applyHistoryItem(item, isUndo, transformableEntityQuery, commands);
+                }
+            } else {
+                for item in items.iter() {
+                    applyHistoryItem(item, isUndo, transformableEntityQuery, commands);
+                }
+            }
+        },
+    };
 }
 
+#[cfg(target_arch = "wasm32")]
 pub fn handleUndoRedo(
     mut runnerWrapper: ResMut<RunnerWrapper>,
     mut transformableEntityQuery: Query<(Entity, &mut Transform)>,
+    mut commands: Commands,
 ) {
     let runner = runnerWrapper.as_mut();
 
@@ -259,47 +1023,25 @@ pub fn handleUndoRedo(
         history.action = HistoryAction::None;
 
         if let Some(historyItem) = if isUndo { history.past.pop() } else { history.future.pop() } {
-            for (entity, mut transform) in transformableEntityQuery.iter_mut() {
-                match historyItem {
-                    HistoryItem::Transform(gizmoTransform, _) => {
-                        if entity != gizmoTransform.0 { continue; }
-    
-                        if isUndo {
-                            match gizmoTransform.1 {
-                                GizmoResult::Translation { delta: _, total } => {
-                                    consoleLog(&format!("Undoing translation from:\n{:?}", transform.translation));
-                                    transform.translation -= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                                    consoleLog(&format!("To:\n{:?}", transform.translation));
-                                },
-                                GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
-                                    transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), -total as f32);
-                                },
-                                GizmoResult::Scale { total } => {
-                                    transform.scale /= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                                },
-                                _ => {},
-                            };
-
-                            history.future.push(historyItem.clone());
-                        } else {
-                            match gizmoTransform.1 {
-                                GizmoResult::Translation { delta: _, total } => {
-                                    transform.translation += Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                                },
-                                GizmoResult::Rotation { axis, delta: _, total, is_view_axis: _ } => {
-                                    transform.rotation *= Quat::from_axis_angle(Vec3::new(axis.x as f32, axis.y as f32, axis.z as f32), total as f32);
-                                },
-                                GizmoResult::Scale { total } => {
-                                    transform.scale *= Vec3::new(total.x as f32, total.y as f32, total.z as f32);
-                                },
-                                _ => {},
-                            };
-
-                            history.past.push(historyItem.clone());
-                        }
-                    },
-                };
+            // Redoing a duplication creates new entities, so the history item is only pushed back once the new copies are known.
+            // Anything further in the future may still point at the old copies, so it can't be redone anymore
+            if let (false, HistoryItem::Duplicate { sources, copies: _ }) = (isUndo, &historyItem) {
+                history.future.clear();
+                let sources = sources.clone();
+                let historyArc = runner.history.clone();
+                commands.queue(move |world: &mut World| {
+                    let copies = duplicateSelection(world, &sources);
+
+                    if let Ok(mut history) = historyArc.write() {
+                        history.past.push(HistoryItem::Duplicate { sources, copies });
+                    }
+                });
+                return;
             }
+
+            applyHistoryItem(&historyItem, isUndo, &mut transformableEntityQuery, &mut commands);
+
+            if isUndo { history.future.push(historyItem); } else { history.past.push(historyItem); }
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::{any, sync::{
     atomic::{
+        AtomicBool,
         AtomicU8, 
         Ordering
     }, Arc, RwLock
@@ -12,14 +13,38 @@ use bevy::{
 };
 use bevy_mod_outline::OutlinePlugin;
 use transform_gizmo_bevy::prelude::*;
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use crate::{
     consoleLog,
-    lib::history::*,
+    EditorHidden,
+    EditorId,
+    EditorLocked,
+    EditorTags,
+    lib::{
+        assetloader::LoadedImages,
+        autosave::RecoverySnapshot,
+        editorflags::{hideEditorHidden, EditorFlag},
+        hierarchy::HierarchyStream,
+        history::*,
+        inspector::InspectorWatches,
+        jscasting::{serdeAsJsObject, FromJs},
+        layers::{EditorLayers, LayerUpdate, OnEditorLayer},
+        marquee::Marquee,
+        pivot::{PivotDrag, PivotMode, PivotSettings},
+        naming::nameInfo,
+        properties::globalTransformInfo,
+        registration::*,
+        scene::SavedScene,
+        scenediff::*,
+        selection::{HoveredEntity, Selection, SelectionMode},
+    },
     systems::{
+        bridge::*,
         startup::*,
         update::*,
     },
+    requestResult,
+    triggerInterfaceCallbacks,
 };
 
 
@@ -66,12 +91,48 @@ pub struct BinaryDataQueue {
     pub image: RwLock<Option<Vec<u8>>>,
 }
 
+// Events from the frontend that need access to the world, handled by `handleBridgeEvents`
+pub enum BridgeEvent {
+    SaveScene,
+    LoadScene(SavedScene, Option<u64>), // the request id is read from next to the scene's own fields
+    SetProperty(PropertyUpdateInfo),
+    SetMaterialProperty(MaterialUpdateInfo),
+    ListComponents,
+    InsertComponent(ComponentUpdateInfo),
+    RemoveComponent(ComponentUpdateInfo),
+    InspectResources,
+    SetResourceProperty(ResourceUpdateInfo),
+    Watch(WatchInfo),
+    Unwatch(WatchInfo),
+    DescribeProtocol,
+    Rename(RenameInfo),
+    Reparent(ReparentInfo),
+    Select(SelectionInfo, SelectionMode),
+    SetEditorFlag(EditorFlagInfo, EditorFlag),
+    ListLayers,
+    UpdateLayer(LayerUpdateInfo),
+    RemoveLayer(LayerUpdateInfo),
+    AssignLayer(LayerAssignmentInfo),
+    Search(SearchInfo),
+    SetPivotMode(PivotModeInfo),
+    PlaceCursor(CursorInfo),
+    HierarchySnapshot,
+}
+
+pub struct RecoveryQueue {
+    // Snapshots the frontend stored from previous sessions
+    pub snapshots: RwLock<Vec<RecoverySnapshot>>,
+    pub restoreRequested: AtomicBool,
+}
+
 // #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub struct Runner {
     gizmoFlags: Arc<AtomicU8>,
     binaryData: Arc<BinaryDataQueue>,
     history: Arc<RwLock<History>>,
+    recovery: Arc<RecoveryQueue>,
+    events: Arc<RwLock<Vec<BridgeEvent>>>,
 }
 
 #[derive(Resource)]
@@ -79,13 +140,143 @@ pub struct RunnerWrapper {
     // pub runner: Arc<Runner>,
     pub binaryData: Arc<BinaryDataQueue>,
     pub history: Arc<RwLock<History>>,
+    pub recovery: Arc<RecoveryQueue>,
+    pub events: Arc<RwLock<Vec<BridgeEvent>>>,
 }
 
-#[derive(serde_derive::Deserialize, serde_derive::Serialize)]
+#[derive(serde_derive::Deserialize)]
+pub struct SceneDiffInfo {
+    pub requestId: Option<u64>, // echoed back in the "diffScenesResult" callback
+    pub before: SavedScene,
+    pub after: SavedScene,
+}
+
+#[derive(serde_derive::Deserialize)]
+pub struct SceneMergeInfo {
+    pub requestId: Option<u64>, // echoed back in the "mergeScenesResult" callback
+    pub base: SavedScene,
+    pub ours: SavedScene,
+    pub theirs: SavedScene,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
 pub struct PropertyUpdateInfo {
-    pub componentName: &str,
-    pub property: &str,
-    pub value: any,
+    pub requestId: Option<u64>, // echoed back in the "setPropertyResult" callback
+    #[serde(default)]
+    pub entity: u64, // entity bits, as sent in the "properties" callback
+    #[serde(default)]
+    pub entities: Vec<u64>, // edits all of these as a single undo step instead of `entity`, for multi-entity inspection
+    pub componentName: String, // component type path
+    pub property: String, // reflection path into the component, e.g. "translation.x", empty for the whole component
+    pub value: serde_json::Value,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct MaterialUpdateInfo {
+    pub requestId: Option<u64>, // echoed back in the "setMaterialPropertyResult" callback
+    pub entity: u64, // entity whose material is edited
+    pub property: String, // reflection path into the material, e.g. "perceptual_roughness"
+    pub value: serde_json::Value, // texture slots take an image id from the "imageLoaded" callback, or null
+    #[serde(default)]
+    pub fork: bool, // give the entity its own copy of the material first, instead of editing it for every entity sharing it
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct ComponentUpdateInfo {
+    pub requestId: Option<u64>, // echoed back in the "insertComponentResult"/"removeComponentResult" callback
+    pub entity: u64,
+    pub componentName: String, // component type path, as sent in the "insertableComponents" callback
+    #[serde(default)]
+    pub value: Option<serde_json::Value>, // value to insert, the component's default is used if left out
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct ResourceUpdateInfo {
+    pub requestId: Option<u64>, // echoed back in the "setResourcePropertyResult" callback
+    pub resourceName: String, // resource type path, as sent in the "resources" callback
+    pub property: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct RenameInfo {
+    pub requestId: Option<u64>, // echoed back in the "renameResult" callback
+    pub entity: u64,
+    pub name: String,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct ReparentInfo {
+    pub requestId: Option<u64>, // echoed back in the "reparentResult" callback
+    pub entity: u64,
+    pub parent: Option<u64>, // null moves the entity to the root
+    pub keepWorldTransform: Option<bool>, // defaults to true, false keeps the local transform so the entity moves along with its new parent
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug, Default)]
+pub struct SelectionInfo {
+    pub requestId: Option<u64>, // echoed back in the "selectionResult" callback
+    #[serde(default)]
+    pub entities: Vec<u64>,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct EditorFlagInfo {
+    pub requestId: Option<u64>, // echoed back in the "setHiddenResult" or "setLockedResult" callback
+    pub entities: Vec<u64>,
+    pub value: bool,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct LayerUpdateInfo {
+    pub requestId: Option<u64>, // echoed back in the "layerResult" callback
+    #[serde(flatten)]
+    pub layer: LayerUpdate, // only the name is used when removing a layer
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct LayerAssignmentInfo {
+    pub requestId: Option<u64>, // echoed back in the "layerResult" callback
+    pub entities: Vec<u64>,
+    pub layer: Option<String>, // null moves the entities off of their layer
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct SearchInfo {
+    pub requestId: Option<u64>, // echoed back in the "searchResult" callback
+    pub query: String,
+    #[serde(default)]
+    pub select: bool, // replaces the selection with everything that was found
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct PivotModeInfo {
+    pub requestId: Option<u64>, // echoed back in the "setPivotModeResult" callback
+    pub mode: PivotMode,
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug)]
+pub struct CursorInfo {
+    pub requestId: Option<u64>, // echoed back in the "placeCursorResult" callback
+    pub position: [f32; 3], // world space
+}
+
+#[derive(serde_derive::Deserialize, serde_derive::Serialize, Clone, Debug, Default)]
+pub struct WatchInfo {
+    pub requestId: Option<u64>, // echoed back in the "watchResult" callback
+    pub entity: Option<u64>, // unwatching without an entity stops watching everything
+    pub components: Option<Vec<String>>, // component type paths, every component is watched if left out
+}
+
+impl PropertyUpdateInfo {
+    pub fn targets(&self) -> Vec<u64> {
+        if self.entities.is_empty() { vec![self.entity] } else { self.entities.clone() }
+    }
+}
+
+// The request id of info that couldn't be read as a whole, so the failure still reaches whoever is waiting on it
+fn requestIdOf(info: &str) -> Option<u64> {
+    serde_json::from_str::<serde_json::Value>(info).ok()?.get("requestId")?.as_u64()
 }
 
 // #[cfg(target_arch = "wasm32")]
@@ -102,6 +293,11 @@ impl Runner {
                 image: RwLock::new(None),
             }),
             history: Arc::new(RwLock::new(History::new())),
+            recovery: Arc::new(RecoveryQueue {
+                snapshots: RwLock::new(vec![]),
+                restoreRequested: AtomicBool::new(false),
+            }),
+            events: Arc::new(RwLock::new(vec![])),
         }
     }
 
@@ -142,12 +338,31 @@ impl Runner {
                 TransformGizmoPlugin,
             ))
 
+            .register_type::<EditorId>()
+            .register_type::<EditorHidden>()
+            .register_type::<EditorLocked>()
+            .register_type::<OnEditorLayer>()
+            .init_resource::<EditorComponents>()
+            .init_resource::<LoadedImages>()
+            .init_resource::<InspectorWatches>()
+            .init_resource::<HierarchyStream>()
+            .init_resource::<Selection>()
+            .init_resource::<EditorLayers>()
+            .init_resource::<Marquee>()
+            .init_resource::<HoveredEntity>()
+            .init_resource::<PivotSettings>()
+            .init_resource::<PivotDrag>()
+            .registerEditorComponentWith::<GlobalTransform>(EditorComponentInfo::named("Global Transform").withSerializer(globalTransformInfo))
+            .registerEditorComponentWith::<Name>(EditorComponentInfo::named("Name").withSerializer(nameInfo))
+            .registerEditorComponentWith::<EditorTags>(EditorComponentInfo::named("Tags").withCategory("Editor"))
             .insert_resource(MeshPickingSettings {
                 require_markers: true,
                 ..default()
             })
             .insert_resource(GizmoOptions {
                 gizmo_orientation: GizmoOrientation::Local,
+                // Several selected entities share one gizmo, transforming them around their median point
+                group_targets: true,
                 ..default()
             })
             .insert_resource(CustomGizmoOptions {
@@ -159,12 +374,15 @@ impl Runner {
             .insert_resource(RunnerWrapper {
                 // Since we're just cloning the arcs, we're creating new references, without actually duplicating any potential data
                 binaryData: self.binaryData.clone(),
-                history: self.history.clone()
+                history: self.history.clone(),
+                recovery: self.recovery.clone(),
+                events: self.events.clone(),
             })
 
-            .add_systems(Startup, (setup, setupDynamicAssets).chain())
-            .add_systems(Update, (syncData, mouseInteractions, keyboardInteractions, handleHistory, handleUndoRedo).chain())
-            .add_systems(PostUpdate, worldFrame)
+            .add_systems(Startup, (setup, setupDynamicAssets, setupAutosave).chain())
+            .add_systems(Update, (syncData, handleBridgeEvents, assignEditorIds, assignDefaultNames, mouseInteractions, marqueeSelection, hoverHighlight, keyboardInteractions, applyPivot, editorCursor, syncPivot, handleHistory, handleUndoRedo).chain())
+            .add_systems(PostUpdate, (worldFrame, pushInspectorUpdates, streamHierarchy, autosave))
+            .add_systems(PostUpdate, hideEditorHidden.after(bevy::render::view::VisibilitySystems::CheckVisibility))
             
             .run();
     }
@@ -181,6 +399,56 @@ impl Runner {
         }
     }
 
+    #[wasm_bindgen]
+    pub fn loadImage(&self, bytes: &[u8]) {
+        if let Ok(mut data) = self.binaryData.image.write() {
+            *data = Some(bytes.to_vec());
+        }
+    }
+
+    // Hands a snapshot that was previously sent through the "autosave" callback back to the game, should be called before `startGame`
+    #[wasm_bindgen]
+    pub fn addRecoverySnapshot(&self, snapshot: &str) {
+        match serde_json::from_str::<RecoverySnapshot>(snapshot) {
+            Ok(snapshot) => if let Ok(mut snapshots) = self.recovery.snapshots.write() {
+                snapshots.push(snapshot);
+            },
+            Err(e) => consoleLog(&format!("Failed to read recovery snapshot: {}", e)),
+        }
+    }
+
+    // Typed counterpart of the "setProperty" event for transforms, taking the objects `IntoJs` produces, `{ x, y, z }` for
+    // translation and scale and `{ x, y, z, w }` for rotation. Answers through "setPropertyResult" like the event does
+    #[wasm_bindgen]
+    pub fn setTransformProperty(&self, requestId: Option<f64>, entity: f64, property: &str, value: JsValue) {
+        let requestId = requestId.map(|id| id as u64);
+        let converted = match property {
+            "translation" | "scale" => Vec3::fromJs(&value).map(|vector| serde_json::json!(vector.to_array())),
+            "rotation" => Quat::fromJs(&value).map(|rotation| serde_json::json!(rotation.to_array())),
+            other => Err(format!("{} is not a transform property, expected translation, rotation or scale", other)),
+        };
+
+        match converted {
+            Ok(value) => self.queueEvent(BridgeEvent::SetProperty(PropertyUpdateInfo {
+                requestId,
+                entity: entity as u64,
+                entities: vec![],
+                componentName: "bevy_transform::components::transform::Transform".to_string(),
+                property: property.to_string(),
+                value,
+            })),
+            Err(e) => requestResult("setPropertyResult", requestId, Err(e)),
+        }
+    }
+
+    fn queueEvent(&self, event: BridgeEvent) {
+        if let Ok(mut events) = self.events.write() {
+            events.push(event);
+        } else {
+            consoleLog("Tried writing simultaneously while queueing an event");
+        }
+    }
+
     #[wasm_bindgen]
     pub fn sendEvent(&self, eventType: &str, info: &str) {
         match eventType {
@@ -197,13 +465,117 @@ impl Runner {
                     consoleLog("Tried writing simultaneously while redoing");
                 }
             },
-            "read" => {
-                if let Ok(history) = self.history.read() {
-                    consoleLog(&format!("Past: {:?}\n\nFuture: {:?}", history.past, history.future));
+            "restoreSnapshot" => {
+                self.recovery.restoreRequested.store(true, Ordering::SeqCst);
+            },
+            "discardSnapshots" => {
+                if let Ok(mut snapshots) = self.recovery.snapshots.write() {
+                    snapshots.clear();
                 }
             },
-            "setProperty" => {
-                let info: PropertyUpdateInfo = serde_json::from_str(info).unwrap();
+            "saveScene" => self.queueEvent(BridgeEvent::SaveScene),
+            "loadScene" => match serde_json::from_str::<SavedScene>(info) {
+                Ok(scene) => self.queueEvent(BridgeEvent::LoadScene(scene, requestIdOf(info))),
+                Err(e) => requestResult("loadSceneResult", requestIdOf(info), Err(format!("Failed to read scene: {}", e))),
+            },
+            // Diffing and merging don't touch the world, so they're answered right away
+            "diffScenes" => match serde_json::from_str::<SceneDiffInfo>(info) {
+                Ok(info) => {
+                    triggerInterfaceCallbacks("sceneDiff", vec![serdeAsJsObject(&diffScenes(&info.before, &info.after))]);
+                    requestResult("diffScenesResult", info.requestId, Ok(()));
+                },
+                Err(e) => requestResult("diffScenesResult", requestIdOf(info), Err(format!("Failed to read scenes to diff: {}", e))),
+            },
+            "mergeScenes" => match serde_json::from_str::<SceneMergeInfo>(info) {
+                Ok(info) => {
+                    triggerInterfaceCallbacks("sceneMerge", vec![serdeAsJsObject(&mergeScenes(&info.base, &info.ours, &info.theirs))]);
+                    requestResult("mergeScenesResult", info.requestId, Ok(()));
+                },
+                Err(e) => requestResult("mergeScenesResult", requestIdOf(info), Err(format!("Failed to read scenes to merge: {}", e))),
+            },
+            "setProperty" => match serde_json::from_str::<PropertyUpdateInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::SetProperty(info)),
+                Err(e) => requestResult("setPropertyResult", requestIdOf(info), Err(format!("Failed to read property update: {}", e))),
+            },
+            "inspectResources" => self.queueEvent(BridgeEvent::InspectResources),
+            "setResourceProperty" => match serde_json::from_str::<ResourceUpdateInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::SetResourceProperty(info)),
+                Err(e) => requestResult("setResourcePropertyResult", requestIdOf(info), Err(format!("Failed to read resource update: {}", e))),
+            },
+            "watch" => match serde_json::from_str::<WatchInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::Watch(info)),
+                Err(e) => requestResult("watchResult", requestIdOf(info), Err(format!("Failed to read watch info: {}", e))),
+            },
+            // Unwatching without a payload stops watching everything
+            "unwatch" if info.trim().is_empty() => self.queueEvent(BridgeEvent::Unwatch(WatchInfo::default())),
+            "unwatch" => match serde_json::from_str::<WatchInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::Unwatch(info)),
+                Err(e) => requestResult("watchResult", requestIdOf(info), Err(format!("Failed to read unwatch info: {}", e))),
+            },
+            "rename" => match serde_json::from_str::<RenameInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::Rename(info)),
+                Err(e) => requestResult("renameResult", requestIdOf(info), Err(format!("Failed to read rename info: {}", e))),
+            },
+            "reparent" => match serde_json::from_str::<ReparentInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::Reparent(info)),
+                Err(e) => requestResult("reparentResult", requestIdOf(info), Err(format!("Failed to read reparent info: {}", e))),
+            },
+            // Deselecting without a payload clears the selection
+            "deselect" if info.trim().is_empty() => self.queueEvent(BridgeEvent::Select(SelectionInfo::default(), SelectionMode::Remove)),
+            "select" | "addToSelection" | "deselect" => {
+                let mode = match eventType {
+                    "select" => SelectionMode::Replace,
+                    "addToSelection" => SelectionMode::Add,
+                    _ => SelectionMode::Remove,
+                };
+                match serde_json::from_str::<SelectionInfo>(info) {
+                    Ok(info) => self.queueEvent(BridgeEvent::Select(info, mode)),
+                    Err(e) => requestResult("selectionResult", requestIdOf(info), Err(format!("Failed to read selection info: {}", e))),
+                }
+            },
+            "setHidden" | "setLocked" => {
+                let flag = if eventType == "setHidden" { EditorFlag::Hidden } else { EditorFlag::Locked };
+                match serde_json::from_str::<EditorFlagInfo>(info) {
+                    Ok(info) => self.queueEvent(BridgeEvent::SetEditorFlag(info, flag)),
+                    Err(e) => requestResult(&format!("{}Result", eventType), requestIdOf(info), Err(format!("Failed to read editor flag info: {}", e))),
+                }
+            },
+            "listLayers" => self.queueEvent(BridgeEvent::ListLayers),
+            "updateLayer" | "removeLayer" => match serde_json::from_str::<LayerUpdateInfo>(info) {
+                Ok(info) if eventType == "updateLayer" => self.queueEvent(BridgeEvent::UpdateLayer(info)),
+                Ok(info) => self.queueEvent(BridgeEvent::RemoveLayer(info)),
+                Err(e) => requestResult("layerResult", requestIdOf(info), Err(format!("Failed to read layer info: {}", e))),
+            },
+            "assignLayer" => match serde_json::from_str::<LayerAssignmentInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::AssignLayer(info)),
+                Err(e) => requestResult("layerResult", requestIdOf(info), Err(format!("Failed to read layer assignment: {}", e))),
+            },
+            "search" => match serde_json::from_str::<SearchInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::Search(info)),
+                Err(e) => requestResult("searchResult", requestIdOf(info), Err(format!("Failed to read search info: {}", e))),
+            },
+            "setPivotMode" => match serde_json::from_str::<PivotModeInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::SetPivotMode(info)),
+                Err(e) => requestResult("setPivotModeResult", requestIdOf(info), Err(format!("Failed to read pivot mode: {}", e))),
+            },
+            "placeCursor" => match serde_json::from_str::<CursorInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::PlaceCursor(info)),
+                Err(e) => requestResult("placeCursorResult", requestIdOf(info), Err(format!("Failed to read cursor position: {}", e))),
+            },
+            "hierarchySnapshot" => self.queueEvent(BridgeEvent::HierarchySnapshot),
+            "describeProtocol" => self.queueEvent(BridgeEvent::DescribeProtocol),
+            "listComponents" => self.queueEvent(BridgeEvent::ListComponents),
+            "insertComponent" => match serde_json::from_str::<ComponentUpdateInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::InsertComponent(info)),
+                Err(e) => requestResult("insertComponentResult", requestIdOf(info), Err(format!("Failed to read component info: {}", e))),
+            },
+            "removeComponent" => match serde_json::from_str::<ComponentUpdateInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::RemoveComponent(info)),
+                Err(e) => requestResult("removeComponentResult", requestIdOf(info), Err(format!("Failed to read component info: {}", e))),
+            },
+            "setMaterialProperty" => match serde_json::from_str::<MaterialUpdateInfo>(info) {
+                Ok(info) => self.queueEvent(BridgeEvent::SetMaterialProperty(info)),
+                Err(e) => requestResult("setMaterialPropertyResult", requestIdOf(info), Err(format!("Failed to read material update: {}", e))),
             },
             _ => {}
         };
@@ -1,6 +1,8 @@
 use js_sys::{Array, Function, Object};
 use wasm_bindgen::prelude::*;
 
+use crate::lib::jscasting::asJsObject;
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
@@ -25,3 +27,20 @@ pub fn triggerInterfaceCallbacks(callbackType: &str, info: Vec<Object>) {
         Function::from(callbacks.get(i)).call1(&JsValue::NULL, &arr).unwrap();
     }
 }
+
+// Answers a request with `{ requestId, success, error }`, the RequestResult shape every "…Result" callback shares
+pub fn requestResult(callbackType: &str, requestId: Option<u64>, result: Result<(), String>) {
+    requestResultWith(callbackType, requestId, result, vec![]);
+}
+
+// Same as `requestResult`, for callbacks that send more fields along with the result
+pub fn requestResultWith(callbackType: &str, requestId: Option<u64>, result: Result<(), String>, fields: Vec<(&str, JsValue)>) {
+    let mut values = vec![
+        ("requestId", requestId.map_or(JsValue::NULL, |id| JsValue::from_f64(id as f64))),
+        ("success", result.is_ok().into()),
+        ("error", result.err().map_or(JsValue::NULL, JsValue::from)),
+    ];
+    values.extend(fields);
+
+    triggerInterfaceCallbacks(callbackType, vec![asJsObject(values)]);
+}